@@ -35,6 +35,9 @@ mod mode_constants {
 
 neobit! {
     /// File open flags compatible with C.
+    ///
+    /// `O_RDONLY` is zero, so it names the empty value in `Debug`.
+    #[neobit(zero = RDONLY)]
     pub struct OpenFlags: CInt {
         const RDONLY   = O_RDONLY;
         const WRONLY   = O_WRONLY;
@@ -67,6 +70,7 @@ fn main() {
 
     println!("Open flags: {:?}", flags);
     println!("Raw flags: {:#x}", flags.bits());
+    println!("Read-only: {:?}", OpenFlags::RDONLY);
 
     #[cfg(unix)]
     {
@@ -88,6 +92,8 @@ fn main() {
         "\nFlags contain CREAT? {}",
         flags.contains(OpenFlags::CREAT)
     );
+    // RDONLY is zero: every value contains it, so compare instead
+    println!("Flags are read-only? {}", flags == OpenFlags::RDONLY);
 
     // Modifying flags
     let mut flags = flags;
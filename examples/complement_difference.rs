@@ -1,6 +1,7 @@
 //! Example demonstrating the complement operation difference
 //!
-//! Shows how neobit and bitflags implement complement() differently.
+//! Shows how neobit and bitflags implement complement() differently, and
+//! how `complement = masked` bridges code migrated from bitflags.
 
 use neobit::neobit;
 
@@ -11,6 +12,15 @@ neobit! {
     }
 }
 
+neobit! {
+    /// Same flags with the complement of bitflags
+    #[neobit(complement = masked)]
+    pub struct MigratedFlags: u8 {
+        const A = 0b01;
+        const B = 0b10;
+    }
+}
+
 fn main() {
     let flags = Flags::A; // 0b01
 
@@ -44,5 +54,12 @@ fn main() {
     println!("Complemented: {:08b}", complemented.bits());
     println!("Error bit preserved: {}", (complemented.bits() & 0x80) != 0);
 
+    // Bridge for code migrated from bitflags
+    println!("\n=== complement = masked ===");
+    let migrated = MigratedFlags::A.complement();
+    println!("Masked complement: {:08b}", migrated.bits());
+    assert_eq!(migrated, MigratedFlags::B);
+    assert_eq!(!MigratedFlags::A, MigratedFlags::B);
+
     println!("\nAll examples passed!");
 }
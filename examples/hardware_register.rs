@@ -48,6 +48,11 @@ neobit! {
     }
 }
 
+impl SpiControl {
+    /// BR[2:0]: baud rate divider field, `f_PCLK / 2^(BR + 1)`
+    pub const BR: Self = Self::from_bits_retain(0b111 << 3);
+}
+
 neobit! {
     /// DMA control register
     pub struct DmaControl: u32 {
@@ -143,11 +148,18 @@ fn main() {
     let current_spi = SpiControl::from_bits_retain(0x0305); // Simulated register value
     println!("Current SPI register: {:?}", current_spi);
 
-    // Modify only specific bits (change baud rate) using a mask
-    let mut new_spi = current_spi;
-    // Clear all baud rate bits first (BR_2 is 0b000, so we need to clear the field)
-    new_spi.remove(SpiControl::BR_4 | SpiControl::BR_8 | SpiControl::BR_16);
-    new_spi.insert(SpiControl::BR_16);
+    // Modify only the baud rate field; every other bit is preserved
+    println!(
+        "Current baud rate divider: /{}",
+        2 << current_spi.field(SpiControl::BR)
+    );
+    let new_spi = current_spi
+        .with_field(SpiControl::BR, 0b011)
+        .expect("baud rate value fits in BR");
+    assert_eq!(
+        new_spi.field(SpiControl::BR),
+        SpiControl::BR_16.field(SpiControl::BR)
+    );
 
     println!("Modified SPI (new baud rate): {:?}", new_spi);
     write_register("SPI1_CR1", new_spi.bits() as u32);
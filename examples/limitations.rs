@@ -6,6 +6,8 @@ use neobit::neobit;
 
 // ✅ GOOD: Single-bit constants only
 neobit! {
+    // `no_metadata` frees the name `ALL` for the constant defined below
+    #[neobit(no_metadata)]
     pub struct Flags: u8 {
         const A = 0b001;     // Single bit - OK
         const B = 0b010;     // Single bit - OK
@@ -5,9 +5,24 @@ use neobit::neobit;
 neobit! {
     /// Unix-style file permissions
     pub struct Permissions: u8 {
-        const READ    = 0b001;
-        const WRITE   = 0b010;
-        const EXECUTE = 0b100;
+        const READ    = 0b001 => 'r';
+        const WRITE   = 0b010 => 'w';
+        const EXECUTE = 0b100 => 'x';
+    }
+}
+
+neobit! {
+    /// Full Unix mode bits, rendered like `ls -l`
+    pub struct FileMode: u16 {
+        const OWNER_READ  = 0o400 => 'r';
+        const OWNER_WRITE = 0o200 => 'w';
+        const OWNER_EXEC  = 0o100 => 'x';
+        const GROUP_READ  = 0o040 => 'r';
+        const GROUP_WRITE = 0o020 => 'w';
+        const GROUP_EXEC  = 0o010 => 'x';
+        const OTHER_READ  = 0o004 => 'r';
+        const OTHER_WRITE = 0o002 => 'w';
+        const OTHER_EXEC  = 0o001 => 'x';
     }
 }
 
@@ -63,4 +78,14 @@ fn main() {
     let restored: Permissions = raw.into();
     assert_eq!(perms, restored);
     println!("Round-trip successful: {} -> {:?}", raw, restored);
+
+    // Character codes
+    println!("Chars: {}", perms.to_char_string());
+    let mode = FileMode::from_bits_retain(0o750);
+    println!("Mode {:o}: {}", mode.bits(), mode.to_char_string());
+    // Output: Mode 750: rwxr-x---
+
+    let parsed = FileMode::from_char_string("rw-r--r--").unwrap();
+    println!("Parsed rw-r--r--: {:o}", parsed.bits());
+    assert_eq!(parsed.bits(), 0o644);
 }
@@ -0,0 +1,1027 @@
+//! # neobit-macros
+//!
+//! The `#[neobit]` attribute, an optional front-end for the `neobit!` macro
+//! of [neobit](https://docs.rs/neobit). Enable it with the `macros` feature
+//! of neobit and use it as `neobit::attr::neobit`.
+//!
+//! The attribute reads an enum or a struct of `bool` fields and expands to
+//! the same `neobit!` invocation that would be written by hand, so the
+//! generated type is identical. On top of that it reports mistakes at the
+//! offending token: unsupported reprs, duplicate names, flags with the same
+//! value, malformed flag attributes and derives of generated traits.
+//!
+//! This crate has no dependencies besides `proc_macro`.
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// Defines a neobit flags type from an enum or a struct of `bool` fields.
+///
+/// The attribute arguments are the `neobit!` options, e.g.
+/// `#[neobit(complement = known, flag_enum = PermissionsFlag)]`, and the
+/// integer type comes from `#[repr(...)]`. The variants of a `flag_enum`
+/// are the listed flags in UpperCamelCase (`READ_ONLY` becomes `ReadOnly`)
+/// unless they are given as in `neobit!`.
+///
+/// Enum variants are the flags. A variant without a value takes the bit
+/// after the previous flag, starting at bit 0:
+///
+/// ```rust,ignore
+/// use neobit::attr::neobit;
+///
+/// #[neobit]
+/// #[repr(u8)]
+/// pub enum Permissions {
+///     /// Read access
+///     READ,                // 0b001
+///     WRITE,               // 0b010
+///     EXECUTE,             // 0b100
+///     #[bit(7)]
+///     #[hidden]
+///     #[vis(pub(crate))]
+///     DIRTY,
+///     #[alias]
+///     EXEC = EXECUTE,
+/// }
+/// ```
+///
+/// In a struct, each `bool` field is a flag named after the field in upper
+/// case (`read_only` becomes `READ_ONLY`), and a field visibility becomes the
+/// flag visibility.
+///
+/// Flag attributes:
+///
+/// - `#[bit(n)]`: the flag is `bit(n)`, counted from the most significant bit
+///   with the `msb0` option
+/// - `#[alias]`: the variant value names the target flag (enums only)
+/// - `#[char('c')]`: display character for `to_char_string()`
+/// - `#[vis(...)]`: visibility of the flag constant (enums only)
+///
+/// Every other attribute, such as `#[doc]`, `#[hidden]` or `#[w1c]`, is
+/// passed on to `neobit!`. A `#[derive]` of a trait the expansion implements
+/// is an error: `Clone`, `Copy`, `PartialEq`, `Eq`, `Hash` and `Default`,
+/// `PartialOrd` and `Ord` unless `no_ord`, and `Debug` unless
+/// `debug = "custom"`.
+///
+/// The expansion names the neobit crate as `::neobit`. When it is renamed or
+/// re-exported, give its path with `crate = path`, e.g.
+/// `#[neobit(crate = my_hal::neobit, msb0)]`.
+///
+/// Flags may overlap, so composites such as `RW = READ | WRITE` are allowed,
+/// but two flags with the same value are rejected: at expansion time when
+/// both values are integer literals or shifts of literals, otherwise with a
+/// compile-time check that points at the later flag. Declare a second name
+/// for the same bits with `#[alias]`.
+#[proc_macro_attribute]
+pub fn neobit(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = match Args::parse(args) {
+        Ok(args) => args,
+        Err(error) => return to_compile_errors(vec![error]),
+    };
+    match Definition::parse(item, args.msb0) {
+        Ok(definition) => match definition.check(&args) {
+            Ok(checks) => definition.expand(args, checks),
+            Err(errors) => to_compile_errors(errors),
+        },
+        Err(errors) => to_compile_errors(errors),
+    }
+}
+
+/// The attribute arguments: the path of the neobit crate and the `neobit!`
+/// options, which are forwarded as they are.
+struct Args {
+    krate: TokenStream,
+    options: Vec<Vec<TokenTree>>,
+    msb0: bool,
+}
+
+impl Args {
+    fn parse(args: TokenStream) -> Result<Self, Error> {
+        let mut krate = parse("::neobit");
+        let mut options = Vec::new();
+        let mut msb0 = false;
+        let mut input = Cursor::new(args, Span::call_site());
+        while !input.is_empty() {
+            let option = input.until_comma();
+            match option.as_slice() {
+                [TokenTree::Ident(name), TokenTree::Punct(eq), path @ ..]
+                    if name.to_string() == "crate" && eq.as_char() == '=' =>
+                {
+                    if path.is_empty() {
+                        return Err(Error::new(
+                            eq.span(),
+                            "expected the path of the neobit crate",
+                        ));
+                    }
+                    krate = path.iter().cloned().collect();
+                    continue;
+                }
+                [TokenTree::Ident(name)] if name.to_string() == "msb0" => msb0 = true,
+                _ => {}
+            }
+            options.push(option);
+        }
+        Ok(Args {
+            krate,
+            options,
+            msb0,
+        })
+    }
+
+    /// The traits `neobit!` implements with these options.
+    fn generated_traits(&self) -> Vec<&'static str> {
+        let mut traits = vec!["Clone", "Copy", "PartialEq", "Eq", "Hash", "Default"];
+        let has = |expected: &str| {
+            self.options.iter().any(|option| {
+                let option: String = option.iter().map(|token| token.to_string()).collect();
+                option == expected
+            })
+        };
+        if !has("no_ord") {
+            traits.extend(["PartialOrd", "Ord"]);
+        }
+        if !has("debug=\"custom\"") {
+            traits.push("Debug");
+        }
+        traits
+    }
+}
+
+/// A diagnostic at a token of the input.
+struct Error {
+    span: Span,
+    message: String,
+}
+
+impl Error {
+    fn new(span: Span, message: impl Into<String>) -> Self {
+        Error {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+fn to_compile_errors(errors: Vec<Error>) -> TokenStream {
+    errors
+        .into_iter()
+        .map(|error| {
+            let message = Literal::string(&error.message).to_string();
+            let tokens = parse(&format!("::core::compile_error! {{ {} }}", message));
+            respan(tokens, error.span)
+        })
+        .collect()
+}
+
+fn parse(source: &str) -> TokenStream {
+    TokenStream::from_str(source).expect("generated tokens are valid")
+}
+
+/// Gives every token of `tokens` the span `span`, so that errors in the
+/// generated code point at the user's code.
+fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            if let TokenTree::Group(group) = &token {
+                let mut inner = Group::new(group.delimiter(), respan(group.stream(), span));
+                inner.set_span(span);
+                token = TokenTree::Group(inner);
+            }
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
+
+fn punct(ch: char, spacing: Spacing) -> TokenTree {
+    TokenTree::Punct(Punct::new(ch, spacing))
+}
+
+fn ident(name: &str) -> TokenTree {
+    TokenTree::Ident(Ident::new(name, Span::call_site()))
+}
+
+/// A cursor over a token stream.
+struct Cursor {
+    tokens: Vec<TokenTree>,
+    pos: usize,
+    end: Span,
+}
+
+impl Cursor {
+    fn new(stream: TokenStream, end: Span) -> Self {
+        Cursor {
+            tokens: stream.into_iter().collect(),
+            pos: 0,
+            end,
+        }
+    }
+
+    fn peek(&self) -> Option<&TokenTree> {
+        self.tokens.get(self.pos)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn span(&self) -> Span {
+        self.peek().map_or(self.end, TokenTree::span)
+    }
+
+    fn bump(&mut self) -> Option<TokenTree> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn is_punct(&self, ch: char) -> bool {
+        matches!(self.peek(), Some(TokenTree::Punct(p)) if p.as_char() == ch)
+    }
+
+    fn is_ident(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(TokenTree::Ident(i)) if i.to_string() == name)
+    }
+
+    fn expect_ident(&mut self, what: &str) -> Result<Ident, Error> {
+        match self.peek() {
+            Some(TokenTree::Ident(i)) => {
+                let i = i.clone();
+                self.pos += 1;
+                Ok(i)
+            }
+            _ => Err(Error::new(self.span(), format!("expected {}", what))),
+        }
+    }
+
+    fn expect_punct(&mut self, ch: char) -> Result<(), Error> {
+        if self.is_punct(ch) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(Error::new(self.span(), format!("expected `{}`", ch)))
+        }
+    }
+
+    /// Parses outer attributes, `#[...]`.
+    fn attrs(&mut self) -> Result<Vec<Group>, Error> {
+        let mut attrs = Vec::new();
+        while self.is_punct('#') {
+            self.pos += 1;
+            match self.bump() {
+                Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Bracket => attrs.push(g),
+                _ => return Err(Error::new(self.span(), "expected an attribute")),
+            }
+        }
+        Ok(attrs)
+    }
+
+    /// Parses an optional visibility, `pub` or `pub(...)`.
+    fn vis(&mut self) -> Vec<TokenTree> {
+        let mut vis = Vec::new();
+        if self.is_ident("pub") {
+            vis.extend(self.bump());
+            if let Some(TokenTree::Group(g)) = self.peek() {
+                if g.delimiter() == Delimiter::Parenthesis {
+                    vis.extend(self.bump());
+                }
+            }
+        }
+        vis
+    }
+
+    /// Takes the tokens up to the next top-level `,` and skips the comma.
+    fn until_comma(&mut self) -> Vec<TokenTree> {
+        let mut tokens = Vec::new();
+        while let Some(token) = self.bump() {
+            match &token {
+                TokenTree::Punct(p) if p.as_char() == ',' => break,
+                _ => tokens.push(token),
+            }
+        }
+        tokens
+    }
+}
+
+/// The integer types accepted by `neobit!`, with their width.
+const REPRS: &[(&str, u32)] = &[
+    ("u8", 8),
+    ("u16", 16),
+    ("u32", 32),
+    ("u64", 64),
+    ("u128", 128),
+    ("i8", 8),
+    ("i16", 16),
+    ("i32", 32),
+    ("i64", 64),
+    ("i128", 128),
+];
+
+/// Returns the value of an integer literal, e.g. `0x80`, `1_000` or `4u8`.
+fn literal_value(literal: &Literal) -> Option<u128> {
+    let text = literal.to_string().replace('_', "");
+    let (digits, radix) = match text.get(..2) {
+        Some("0x") => (&text[2..], 16),
+        Some("0o") => (&text[2..], 8),
+        Some("0b") => (&text[2..], 2),
+        _ => (&text[..], 10),
+    };
+    // Integer suffixes start with `u` or `i`, which are not hex digits
+    let digits = match digits.find(|c| c == 'u' || c == 'i') {
+        Some(suffix) => &digits[..suffix],
+        None => digits,
+    };
+    u128::from_str_radix(digits, radix).ok()
+}
+
+/// How `bit(n)` positions are counted: from the least significant bit, or
+/// from the most significant one with the `msb0` option.
+#[derive(Copy, Clone)]
+struct Numbering {
+    width: u32,
+    msb0: bool,
+}
+
+impl Numbering {
+    /// Returns the mask of bit `position`, or `None` if it is out of range.
+    fn mask(self, position: u128) -> Option<u128> {
+        if position >= u128::from(self.width) {
+            None
+        } else if self.msb0 {
+            Some(1 << (u128::from(self.width) - 1 - position))
+        } else {
+            Some(1 << position)
+        }
+    }
+}
+
+/// Evaluates a flag value made of an integer literal, a shift of two
+/// literals or `bit(n)`, the forms that can be checked at expansion time.
+fn evaluate(tokens: &[TokenTree], numbering: Numbering) -> Option<u128> {
+    match tokens {
+        [TokenTree::Literal(value)] => literal_value(value),
+        [TokenTree::Group(group)] if group.delimiter() != Delimiter::Brace => {
+            let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+            evaluate(&inner, numbering)
+        }
+        [TokenTree::Ident(function), TokenTree::Group(args)]
+            if function.to_string() == "bit" && args.delimiter() == Delimiter::Parenthesis =>
+        {
+            match args.stream().into_iter().collect::<Vec<_>>().as_slice() {
+                [TokenTree::Literal(position)] => numbering.mask(literal_value(position)?),
+                _ => None,
+            }
+        }
+        [TokenTree::Literal(value), TokenTree::Punct(first), TokenTree::Punct(second), TokenTree::Literal(shift)]
+            if first.as_char() == '<'
+                && first.spacing() == Spacing::Joint
+                && second.as_char() == '<' =>
+        {
+            let shift = literal_value(shift)?;
+            if shift >= 128 {
+                return None;
+            }
+            literal_value(value)?.checked_mul(1 << shift)
+        }
+        _ => None,
+    }
+}
+
+fn value_span(tokens: &[TokenTree], default: Span) -> Span {
+    tokens.first().map_or(default, TokenTree::span)
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Kind {
+    Const,
+    Alias,
+}
+
+struct Flag {
+    /// Attributes passed on to `neobit!`, as `[...]` groups.
+    attrs: Vec<Group>,
+    vis: Vec<TokenTree>,
+    kind: Kind,
+    name: Ident,
+    value: Vec<TokenTree>,
+    /// The value, if it is known at expansion time.
+    bits: Option<u128>,
+    display_char: Option<Literal>,
+    /// Flags under `#[cfg]` may not exist, so they are not checked.
+    conditional: bool,
+}
+
+/// A flag before its value is resolved.
+struct Declaration {
+    flag: Flag,
+    value: Option<Vec<TokenTree>>,
+    bit: Option<(u128, Span)>,
+}
+
+struct Definition {
+    attrs: Vec<Group>,
+    vis: Vec<TokenTree>,
+    name: Ident,
+    repr: Ident,
+    flags: Vec<Flag>,
+}
+
+impl Definition {
+    fn parse(item: TokenStream, msb0: bool) -> Result<Self, Vec<Error>> {
+        let mut input = Cursor::new(item, Span::call_site());
+        let mut errors = Vec::new();
+
+        let mut attrs = Vec::new();
+        let mut repr = None;
+        for attr in input.attrs().map_err(|e| vec![e])? {
+            match repr_types(&attr) {
+                Some(types) => {
+                    for ty in types {
+                        if ty.to_string() == "transparent" {
+                            attrs.push(attr.clone());
+                        } else if repr.is_some() {
+                            errors.push(Error::new(ty.span(), "duplicate integer repr"));
+                        } else {
+                            repr = Some(ty);
+                        }
+                    }
+                }
+                None => attrs.push(attr),
+            }
+        }
+
+        let vis = input.vis();
+        let is_enum = if input.is_ident("enum") {
+            true
+        } else if input.is_ident("struct") {
+            false
+        } else {
+            return Err(vec![Error::new(
+                input.span(),
+                "#[neobit] expects an enum or a struct",
+            )]);
+        };
+        input.bump();
+        let name = input.expect_ident("a type name").map_err(|e| vec![e])?;
+
+        let body = match input.bump() {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => g,
+            Some(TokenTree::Punct(p)) if p.as_char() == '<' => {
+                return Err(vec![Error::new(
+                    p.span(),
+                    "generic flag types are not supported",
+                )]);
+            }
+            other => {
+                let span = other.map_or(name.span(), |t| t.span());
+                let expected = if is_enum {
+                    "expected the variants in braces"
+                } else {
+                    "expected named `bool` fields in braces"
+                };
+                return Err(vec![Error::new(span, expected)]);
+            }
+        };
+
+        let (repr, width) = match repr {
+            Some(repr) => match REPRS.iter().find(|(ty, _)| repr.to_string() == *ty) {
+                Some(&(_, width)) => (repr, width),
+                None => {
+                    errors.push(Error::new(
+                        repr.span(),
+                        format!(
+                            "unsupported repr `{}`, expected one of u8, u16, u32, u64, u128, \
+                             i8, i16, i32, i64, i128",
+                            repr
+                        ),
+                    ));
+                    (repr, 128)
+                }
+            },
+            None => {
+                errors.push(Error::new(
+                    name.span(),
+                    "missing integer repr, add e.g. `#[repr(u32)]`",
+                ));
+                (Ident::new("u128", name.span()), 128)
+            }
+        };
+
+        let mut body = Cursor::new(body.stream(), body.span());
+        let mut declarations = Vec::new();
+        while !body.is_empty() {
+            let declaration = if is_enum {
+                parse_variant(&mut body)
+            } else {
+                parse_field(&mut body)
+            };
+            match declaration {
+                Ok(declaration) => declarations.push(declaration),
+                Err(error) => {
+                    errors.push(error);
+                    body.until_comma();
+                }
+            }
+        }
+
+        let flags = resolve_values(declarations, Numbering { width, msb0 }, &mut errors);
+        if errors.is_empty() {
+            Ok(Definition {
+                attrs,
+                vis,
+                name,
+                repr,
+                flags,
+            })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Reports derives of generated traits, duplicate names and duplicate
+    /// literal values, and returns the pairs of flags whose values must be
+    /// compared by the compiler.
+    fn check(&self, args: &Args) -> Result<Vec<(usize, usize)>, Vec<Error>> {
+        let mut errors = Vec::new();
+        let generated = args.generated_traits();
+        for attr in &self.attrs {
+            for derive in derived_traits(attr) {
+                if generated.contains(&derive.to_string().as_str()) {
+                    errors.push(Error::new(
+                        derive.span(),
+                        format!(
+                            "`{}` is implemented by `#[neobit]`, remove the derive",
+                            derive
+                        ),
+                    ));
+                }
+            }
+        }
+
+        let mut names = HashSet::new();
+        for flag in &self.flags {
+            if !names.insert(flag.name.to_string()) && !flag.conditional {
+                errors.push(Error::new(
+                    flag.name.span(),
+                    format!("duplicate flag name `{}`", flag.name),
+                ));
+            }
+        }
+
+        let mut checks = Vec::new();
+        for (later, flag) in self.flags.iter().enumerate() {
+            if flag.kind == Kind::Alias || flag.conditional {
+                continue;
+            }
+            for (earlier, other) in self.flags[..later].iter().enumerate() {
+                if other.kind == Kind::Alias || other.conditional {
+                    continue;
+                }
+                match (other.bits, flag.bits) {
+                    (Some(a), Some(b)) if a == b => errors.push(Error::new(
+                        value_span(&flag.value, flag.name.span()),
+                        format!(
+                            "`{}` has the same value as `{}` ({:#x}); use `#[alias]` for another \
+                             name of the same flag",
+                            flag.name, other.name, a
+                        ),
+                    )),
+                    (Some(_), Some(_)) => {}
+                    _ => checks.push((earlier, later)),
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(checks)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The `flag_enum` variants: the listed flags, i.e. neither aliases nor
+    /// `#[hidden]`, in UpperCamelCase.
+    fn variants(&self) -> TokenStream {
+        let mut variants = Vec::new();
+        for flag in &self.flags {
+            let hidden = flag.attrs.iter().any(|attr| {
+                matches!(attr.stream().into_iter().next(),
+                    Some(TokenTree::Ident(name)) if name.to_string() == "hidden")
+            });
+            if flag.kind == Kind::Alias || hidden {
+                continue;
+            }
+            if !variants.is_empty() {
+                variants.push(punct(',', Spacing::Alone));
+            }
+            variants.push(TokenTree::Ident(Ident::new(
+                &upper_camel_case(&flag.name.to_string()),
+                flag.name.span(),
+            )));
+        }
+        variants.into_iter().collect()
+    }
+
+    fn expand(&self, args: Args, checks: Vec<(usize, usize)>) -> TokenStream {
+        let mut inner = Vec::new();
+        if !args.options.is_empty() {
+            let mut options = Vec::new();
+            for mut option in args.options {
+                if let [TokenTree::Ident(name), TokenTree::Punct(eq), TokenTree::Ident(_)] =
+                    option.as_slice()
+                {
+                    if name.to_string() == "flag_enum" && eq.as_char() == '=' {
+                        option.push(TokenTree::Group(Group::new(
+                            Delimiter::Brace,
+                            self.variants(),
+                        )));
+                    }
+                }
+                if !options.is_empty() {
+                    options.push(punct(',', Spacing::Alone));
+                }
+                options.extend(option);
+            }
+            inner.push(punct('#', Spacing::Alone));
+            let option = vec![
+                ident("neobit"),
+                TokenTree::Group(Group::new(
+                    Delimiter::Parenthesis,
+                    options.into_iter().collect(),
+                )),
+            ];
+            inner.push(TokenTree::Group(Group::new(
+                Delimiter::Bracket,
+                option.into_iter().collect(),
+            )));
+        }
+        for attr in &self.attrs {
+            inner.push(punct('#', Spacing::Alone));
+            inner.push(TokenTree::Group(attr.clone()));
+        }
+        inner.extend(self.vis.iter().cloned());
+        inner.push(ident("struct"));
+        inner.push(TokenTree::Ident(self.name.clone()));
+        inner.push(punct(':', Spacing::Alone));
+        inner.push(TokenTree::Ident(self.repr.clone()));
+
+        let mut body = Vec::new();
+        for flag in &self.flags {
+            for attr in &flag.attrs {
+                body.push(punct('#', Spacing::Alone));
+                body.push(TokenTree::Group(attr.clone()));
+            }
+            body.extend(flag.vis.iter().cloned());
+            body.push(ident(match flag.kind {
+                Kind::Const => "const",
+                Kind::Alias => "alias",
+            }));
+            body.push(TokenTree::Ident(flag.name.clone()));
+            body.push(punct('=', Spacing::Alone));
+            body.extend(flag.value.iter().cloned());
+            if let Some(display_char) = &flag.display_char {
+                body.push(punct('=', Spacing::Joint));
+                body.push(punct('>', Spacing::Alone));
+                body.push(TokenTree::Literal(display_char.clone()));
+            }
+            body.push(punct(';', Spacing::Alone));
+        }
+        inner.push(TokenTree::Group(Group::new(
+            Delimiter::Brace,
+            body.into_iter().collect(),
+        )));
+
+        let mut output = args.krate;
+        output.extend(parse("::neobit!"));
+        output.extend(Some(TokenTree::Group(Group::new(
+            Delimiter::Brace,
+            inner.into_iter().collect(),
+        ))));
+
+        // Evaluated by the compiler: indexing `[()]` with 1 fails at the
+        // later of two flags with the same value.
+        for (earlier, later) in checks {
+            let (a, b) = (&self.flags[earlier], &self.flags[later]);
+            let check = parse(&format!(
+                "#[allow(deprecated, non_upper_case_globals)] const _: () = {{ \
+                 const {2}_duplicates_{1}: () = [()][({0}::{1}.bits() == {0}::{2}.bits()) as usize]; \
+                 {2}_duplicates_{1} }};",
+                self.name, a.name, b.name
+            ));
+            output.extend(respan(check, b.name.span()));
+        }
+        output
+    }
+}
+
+/// Converts a flag name such as `READ_ONLY` to `ReadOnly`.
+fn upper_camel_case(name: &str) -> String {
+    name.split('_')
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars.map(|c| c.to_ascii_lowercase()))
+        })
+        .collect()
+}
+
+/// Returns the types of a `repr(...)` attribute, or `None` for other
+/// attributes.
+fn repr_types(attr: &Group) -> Option<Vec<Ident>> {
+    let mut tokens = attr.stream().into_iter();
+    match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(name)), Some(TokenTree::Group(args)), None)
+            if name.to_string() == "repr" && args.delimiter() == Delimiter::Parenthesis =>
+        {
+            Some(
+                args.stream()
+                    .into_iter()
+                    .filter_map(|token| match token {
+                        TokenTree::Ident(ty) => Some(ty),
+                        _ => None,
+                    })
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Returns the last path segment of each trait of a `derive(...)` attribute,
+/// e.g. `Debug` for `core::fmt::Debug`.
+fn derived_traits(attr: &Group) -> Vec<Ident> {
+    let mut tokens = attr.stream().into_iter();
+    match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(name)), Some(TokenTree::Group(args)), None)
+            if name.to_string() == "derive" && args.delimiter() == Delimiter::Parenthesis =>
+        {
+            let mut cursor = Cursor::new(args.stream(), args.span());
+            let mut traits = Vec::new();
+            while !cursor.is_empty() {
+                let path = cursor.until_comma();
+                if let Some(TokenTree::Ident(name)) = path.last() {
+                    traits.push(name.clone());
+                }
+            }
+            traits
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The `#[bit]`, `#[alias]`, `#[char]` and `#[vis]` attributes of a flag.
+#[derive(Default)]
+struct FlagAttrs {
+    passed: Vec<Group>,
+    alias: Option<Span>,
+    bit: Option<(u128, Span)>,
+    display_char: Option<Literal>,
+    vis: Option<Vec<TokenTree>>,
+    conditional: bool,
+}
+
+fn parse_flag_attrs(attrs: Vec<Group>) -> Result<FlagAttrs, Error> {
+    let mut parsed = FlagAttrs::default();
+    for attr in attrs {
+        let tokens: Vec<TokenTree> = attr.stream().into_iter().collect();
+        let name = match tokens.first() {
+            Some(TokenTree::Ident(name)) => name.to_string(),
+            _ => String::new(),
+        };
+        let args = match tokens.get(1) {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+                Some(g.stream().into_iter().collect::<Vec<_>>())
+            }
+            _ => None,
+        };
+        match (name.as_str(), tokens.len(), args) {
+            ("alias", 1, _) => parsed.alias = Some(attr.span()),
+            ("bit", 2, Some(args)) => match args.as_slice() {
+                [TokenTree::Literal(bit)] if literal_value(bit).is_some() => {
+                    parsed.bit = Some((literal_value(bit).unwrap_or(0), bit.span()));
+                }
+                _ => return Err(Error::new(attr.span(), "expected `#[bit(n)]`")),
+            },
+            ("char", 2, Some(args)) => match args.as_slice() {
+                [TokenTree::Literal(c)] if c.to_string().starts_with('\'') => {
+                    parsed.display_char = Some(c.clone());
+                }
+                _ => return Err(Error::new(attr.span(), "expected `#[char('c')]`")),
+            },
+            ("vis", 2, Some(args)) => parsed.vis = Some(args),
+            ("alias", _, _) | ("bit", _, _) | ("char", _, _) | ("vis", _, _) => {
+                return Err(Error::new(
+                    attr.span(),
+                    format!("malformed `#[{}]` attribute", name),
+                ));
+            }
+            ("cfg", _, _) => {
+                parsed.conditional = true;
+                parsed.passed.push(attr);
+            }
+            _ => parsed.passed.push(attr),
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_variant(input: &mut Cursor) -> Result<Declaration, Error> {
+    let attrs = parse_flag_attrs(input.attrs()?)?;
+    let name = input.expect_ident("a flag name")?;
+    if let Some(TokenTree::Group(fields)) = input.peek() {
+        return Err(Error::new(
+            fields.span(),
+            "flag variants cannot have fields",
+        ));
+    }
+    let value = if input.is_punct('=') {
+        input.bump();
+        let value = input.until_comma();
+        if value.is_empty() {
+            return Err(Error::new(input.span(), "expected a flag value"));
+        }
+        Some(value)
+    } else {
+        if !input.is_empty() {
+            input.expect_punct(',')?;
+        }
+        None
+    };
+
+    let kind = match (attrs.alias, &value) {
+        (Some(_), Some(_)) => Kind::Alias,
+        (Some(span), None) => {
+            return Err(Error::new(
+                span,
+                "`#[alias]` needs the target, e.g. `FSYNC = SYNC`",
+            ));
+        }
+        (None, _) => Kind::Const,
+    };
+    if let (Some((_, span)), Some(_)) = (attrs.bit, &value) {
+        return Err(Error::new(
+            span,
+            "a flag with a value cannot also have `#[bit]`",
+        ));
+    }
+
+    Ok(Declaration {
+        flag: Flag {
+            attrs: attrs.passed,
+            vis: attrs.vis.unwrap_or_default(),
+            kind,
+            name,
+            value: Vec::new(),
+            bits: None,
+            display_char: attrs.display_char,
+            conditional: attrs.conditional,
+        },
+        value,
+        bit: attrs.bit,
+    })
+}
+
+fn parse_field(input: &mut Cursor) -> Result<Declaration, Error> {
+    let attrs = parse_flag_attrs(input.attrs()?)?;
+    if let Some(span) = attrs.alias {
+        return Err(Error::new(
+            span,
+            "`#[alias]` is only supported on enum variants",
+        ));
+    }
+    let mut vis = input.vis();
+    if let Some(attr_vis) = attrs.vis {
+        if !vis.is_empty() {
+            return Err(Error::new(
+                vis[0].span(),
+                "use either the field visibility or `#[vis]`",
+            ));
+        }
+        vis = attr_vis;
+    }
+    let field = input.expect_ident("a field name")?;
+    input.expect_punct(':')?;
+    let ty = input.until_comma();
+    match ty.as_slice() {
+        [TokenTree::Ident(ty)] if ty.to_string() == "bool" => {}
+        _ => {
+            return Err(Error::new(
+                value_span(&ty, field.span()),
+                "flag fields must have type `bool`",
+            ));
+        }
+    }
+
+    let text = field.to_string();
+    let upper = text.trim_start_matches("r#").to_ascii_uppercase();
+    Ok(Declaration {
+        flag: Flag {
+            attrs: attrs.passed,
+            vis,
+            kind: Kind::Const,
+            name: Ident::new(&upper, field.span()),
+            value: Vec::new(),
+            bits: None,
+            display_char: attrs.display_char,
+            conditional: attrs.conditional,
+        },
+        value: None,
+        bit: attrs.bit,
+    })
+}
+
+/// Assigns the flag values: `#[bit(n)]`, the written value, or the bit after
+/// the previous flag. Implicit bits always count from the least significant
+/// bit.
+fn resolve_values(
+    declarations: Vec<Declaration>,
+    numbering: Numbering,
+    errors: &mut Vec<Error>,
+) -> Vec<Flag> {
+    let width = numbering.width;
+    // The next implicit bit, or `None` after a value that is not a literal
+    let mut next_bit = Some(0u32);
+    let mut flags = Vec::new();
+    for declaration in declarations {
+        let mut flag = declaration.flag;
+        let span = flag.name.span();
+        let bit = match (declaration.bit, &declaration.value) {
+            (Some((bit, bit_span)), _) => Some((bit, bit_span, true)),
+            (None, None) => match next_bit {
+                Some(bit) => Some((u128::from(bit), span, false)),
+                None => {
+                    errors.push(Error::new(
+                        span,
+                        format!(
+                            "cannot infer the bit of `{}` after a flag whose value is not a \
+                             literal, use `#[bit(n)]` or an explicit value",
+                            flag.name
+                        ),
+                    ));
+                    continue;
+                }
+            },
+            (None, Some(_)) => None,
+        };
+
+        match (bit, declaration.value) {
+            (Some((bit, bit_span, explicit)), _) => {
+                let mask = if explicit {
+                    numbering.mask(bit)
+                } else {
+                    Numbering { width, msb0: false }.mask(bit)
+                };
+                let mask = match mask {
+                    Some(mask) => mask,
+                    None => {
+                        errors.push(Error::new(
+                            bit_span,
+                            format!(
+                                "bit {} of `{}` does not fit in {} bits",
+                                bit, flag.name, width
+                            ),
+                        ));
+                        continue;
+                    }
+                };
+                // `bit(n)` follows the numbering of the type
+                let value = if explicit {
+                    parse(&format!("bit({})", bit))
+                } else {
+                    parse(&format!("1 << {}", bit))
+                };
+                flag.value = respan(value, bit_span).into_iter().collect();
+                flag.bits = Some(mask);
+            }
+            (None, Some(value)) => {
+                if flag.kind == Kind::Const {
+                    flag.bits = evaluate(&value, numbering);
+                }
+                flag.value = value;
+            }
+            (None, None) => unreachable!("implicit values get a bit"),
+        }
+
+        if flag.kind == Kind::Const {
+            if let Some(bits) = flag.bits {
+                if width < 128 && bits >> width != 0 {
+                    errors.push(Error::new(
+                        value_span(&flag.value, span),
+                        format!(
+                            "the value of `{}` does not fit in {} bits",
+                            flag.name, width
+                        ),
+                    ));
+                }
+            }
+            next_bit = flag.bits.map(|bits| 128 - bits.leading_zeros());
+        }
+        flags.push(flag);
+    }
+    flags
+}
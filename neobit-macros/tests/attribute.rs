@@ -0,0 +1,175 @@
+use neobit_macros::neobit;
+
+#[neobit]
+#[repr(u8)]
+pub enum Permissions {
+    /// Read access
+    READ,
+    WRITE,
+    EXECUTE,
+    #[bit(7)]
+    #[hidden]
+    DIRTY,
+    #[alias]
+    EXEC = EXECUTE,
+}
+
+#[neobit(complement = known, flag_enum = ModeFlag)]
+#[repr(u32)]
+pub enum Mode {
+    #[char('r')]
+    READ = 0x10,
+    #[char('w')]
+    WRITE,
+    SYNC = 1 << 8,
+    APPEND,
+    #[vis(pub(crate))]
+    INTERNAL = Mode::SYNC.bits() << 4,
+    #[bit(20)]
+    LATE,
+}
+
+// `no_metadata` frees the name `ALL` for a flag
+#[neobit(no_metadata)]
+#[repr(u8)]
+pub enum Access {
+    READ,
+    WRITE,
+    RW = Access::READ.bits() | Access::WRITE.bits(),
+    ALL = 0b111,
+    EXECUTE = 0b100,
+}
+
+#[neobit(no_from)]
+#[repr(i8)]
+pub struct Status {
+    ready: bool,
+    pub busy: bool,
+    #[bit(7)]
+    error: bool,
+}
+
+#[neobit(flag_enum = LinkFlag)]
+#[repr(u8)]
+pub struct Link {
+    link_up: bool,
+    #[hidden]
+    test_mode: bool,
+    full_duplex: bool,
+}
+
+#[neobit(msb0)]
+#[repr(u16)]
+pub enum Msr {
+    #[bit(0)]
+    SF,
+    EE = bit(8),
+    PR,
+}
+
+mod hal {
+    pub use ::neobit as flags;
+}
+
+#[neobit(crate = crate::hal::flags, msb0)]
+#[repr(u8)]
+pub enum Control {
+    #[bit(0)]
+    ENABLE,
+}
+
+// `msb0` is only an option on its own, not as the value of another option
+#[neobit(consts = msb0)]
+#[repr(u8)]
+pub enum Lines {
+    #[bit(0)]
+    RX,
+}
+
+#[test]
+fn test_implicit_bits() {
+    assert_eq!(Permissions::READ.bits(), 0b001);
+    assert_eq!(Permissions::WRITE.bits(), 0b010);
+    assert_eq!(Permissions::EXECUTE.bits(), 0b100);
+    assert_eq!(Permissions::EXEC, Permissions::EXECUTE);
+}
+
+#[test]
+fn test_hidden_flag() {
+    assert_eq!(Permissions::DIRTY.bits(), 0x80);
+    assert_eq!(Permissions::all().bits(), 0b111);
+    assert_eq!(
+        format!("{:?}", Permissions::READ | Permissions::EXEC),
+        "Permissions(READ | EXECUTE)"
+    );
+}
+
+#[test]
+fn test_bits_follow_previous_value() {
+    assert_eq!(Mode::WRITE.bits(), 0x20);
+    assert_eq!(Mode::APPEND.bits(), 0x200);
+    assert_eq!(Mode::INTERNAL.bits(), 0x1000);
+    assert_eq!(Mode::LATE.bits(), 1 << 20);
+}
+
+#[test]
+fn test_options_are_forwarded() {
+    assert_eq!(!Mode::READ, Mode::all() - Mode::READ);
+    assert_eq!(Mode::from(ModeFlag::Sync), Mode::SYNC);
+    assert_eq!((Mode::READ | Mode::WRITE).to_char_string().as_str(), "rw");
+}
+
+#[test]
+fn test_flag_enum_variants() {
+    assert_eq!(
+        ModeFlag::ALL_VARIANTS,
+        &[
+            ModeFlag::Read,
+            ModeFlag::Write,
+            ModeFlag::Sync,
+            ModeFlag::Append,
+            ModeFlag::Internal,
+            ModeFlag::Late,
+        ]
+    );
+    assert_eq!(
+        LinkFlag::ALL_VARIANTS,
+        &[LinkFlag::LinkUp, LinkFlag::FullDuplex]
+    );
+    assert_eq!(LinkFlag::FullDuplex.flag(), Link::FULL_DUPLEX);
+    assert_eq!(LinkFlag::LinkUp.name(), "LINK_UP");
+}
+
+#[test]
+fn test_composite_flags() {
+    assert_eq!(Access::RW, Access::READ | Access::WRITE);
+    assert_eq!(Access::ALL.bits(), 0b111);
+    assert_eq!(Access::EXECUTE.bits(), 0b100);
+    assert_eq!(format!("{:?}", Access::RW), "Access(READ | WRITE)");
+}
+
+#[test]
+fn test_struct_fields() {
+    assert_eq!(Status::READY.bits(), 0b01);
+    assert_eq!(Status::BUSY.bits(), 0b10);
+    assert_eq!(Status::ERROR.bits(), i8::MIN);
+    assert_eq!(
+        format!("{:?}", Status::READY | Status::ERROR),
+        "Status(READY | ERROR)"
+    );
+}
+
+#[test]
+fn test_msb0_positions() {
+    assert_eq!(Msr::SF.bits(), 0x8000);
+    assert_eq!(Msr::EE.bits(), 0x0080);
+    // Implicit bits count from the least significant bit
+    assert_eq!(Msr::PR.bits(), 0x0100);
+}
+
+#[test]
+fn test_crate_path() {
+    assert_eq!(Control::ENABLE.bits(), 0x80);
+    assert_eq!(Lines::RX.bits(), 0x01);
+    assert_eq!(msb0::RX, 0x01);
+}
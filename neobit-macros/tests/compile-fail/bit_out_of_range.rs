@@ -0,0 +1,12 @@
+use neobit::attr::neobit;
+
+#[neobit]
+#[repr(u8)]
+pub enum Permissions {
+    READ,
+    #[bit(8)]
+    WRITE,
+    EXECUTE = 0x100,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use neobit::attr::neobit;
+
+#[neobit(crate =)]
+#[repr(u8)]
+pub enum Permissions {
+    READ,
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+use neobit::attr::neobit;
+
+#[neobit]
+#[derive(Debug)]
+#[repr(u8)]
+pub enum Permissions {
+    READ,
+    WRITE,
+}
+
+#[neobit(no_ord)]
+#[derive(Clone, core::hash::Hash, PartialOrd)]
+#[repr(u8)]
+pub struct Status {
+    ready: bool,
+}
+
+#[neobit(debug = "custom")]
+#[derive(Debug)]
+#[repr(u8)]
+pub enum Mode {
+    SYNC,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use neobit::attr::neobit;
+
+const BASE: u8 = 0b100;
+
+#[neobit]
+#[repr(u8)]
+pub enum Permissions {
+    READ = BASE,
+    WRITE = BASE << 1,
+    EXECUTE = BASE,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use neobit::attr::neobit;
+
+#[neobit]
+#[repr(u8)]
+pub enum Permissions {
+    READ,
+    WRITE,
+    READ,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use neobit::attr::neobit;
+
+#[neobit]
+#[repr(u8)]
+pub enum Permissions {
+    READ = 0b001,
+    WRITE = 0b001,
+    RW = 0b011,
+}
+
+fn main() {}
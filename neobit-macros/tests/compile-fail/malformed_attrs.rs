@@ -0,0 +1,24 @@
+use neobit::attr::neobit;
+
+#[neobit]
+#[repr(u8)]
+pub enum Permissions {
+    #[bit]
+    READ,
+}
+
+#[neobit]
+#[repr(u8)]
+pub enum Mode {
+    #[alias]
+    SYNC,
+}
+
+#[neobit]
+#[repr(u8)]
+pub struct Status {
+    ready: bool,
+    level: u8,
+}
+
+fn main() {}
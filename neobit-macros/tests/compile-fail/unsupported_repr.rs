@@ -0,0 +1,9 @@
+use neobit::attr::neobit;
+
+#[neobit]
+#[repr(usize)]
+pub enum Permissions {
+    READ,
+}
+
+fn main() {}
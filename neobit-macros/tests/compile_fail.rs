@@ -0,0 +1,85 @@
+//! Checks the diagnostics of `#[neobit]`: every file in `tests/compile-fail`
+//! is built as the `main.rs` of a scratch crate, and the errors reported in
+//! it must be exactly the lines of the `.stderr` file next to it, written as
+//! `src/main.rs:line:column: error: message`. Errors raised by rustc itself
+//! keep only their code, e.g. `error[E0080]`, as the wording changes between
+//! compiler versions.
+//!
+//! The scratch crates resolve the dependencies of the real workspace and the
+//! expected errors follow the current diagnostics, so the test is ignored by
+//! default. Run it with `cargo test -p neobit-macros -- --ignored`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn errors(case: &Path, scratch: &Path, target: &Path) -> Vec<String> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let name = case.file_stem().unwrap().to_str().unwrap();
+    let dir = scratch.join(name);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n\
+             [dependencies]\nneobit = {{ path = {:?}, features = [\"macros\"] }}\n\n\
+             [workspace]\n",
+            name.replace('_', "-"),
+            root.canonicalize().unwrap(),
+        ),
+    )
+    .unwrap();
+    fs::copy(case, dir.join("src/main.rs")).unwrap();
+
+    let output = Command::new(env!("CARGO"))
+        .args(["check", "--quiet", "--message-format", "short"])
+        .arg("--target-dir")
+        .arg(target)
+        .current_dir(&dir)
+        .env_remove("RUSTFLAGS")
+        .env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{} compiled", case.display());
+    String::from_utf8(output.stderr)
+        .unwrap()
+        .lines()
+        .filter(|line| line.starts_with("src/main.rs:") && line.contains(": error"))
+        .map(|line| match line.find("error[") {
+            Some(start) => line[..line[start..].find(']').unwrap() + start + 1].to_owned(),
+            None => line.to_owned(),
+        })
+        .collect()
+}
+
+#[test]
+#[ignore = "builds scratch crates against the workspace"]
+fn test_compile_fail() {
+    let cases = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/compile-fail");
+    let scratch = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("compile-fail");
+    let target = scratch.join("target");
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(&cases)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "rs"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    let mut failures = Vec::new();
+    for case in &paths {
+        let expected = fs::read_to_string(case.with_extension("stderr")).unwrap_or_default();
+        let expected: Vec<&str> = expected.lines().collect();
+        let actual = errors(case, &scratch, &target);
+        if actual != expected {
+            failures.push(format!(
+                "{}:\nexpected:\n{}\nactual:\n{}",
+                case.display(),
+                expected.join("\n"),
+                actual.join("\n")
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
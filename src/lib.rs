@@ -63,6 +63,1421 @@
 
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+/// Error returned when a fixed-size buffer is too small for the rendered text.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct BufferTooSmall;
+
+impl core::fmt::Display for BufferTooSmall {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("buffer too small")
+    }
+}
+
+/// Error returned by the generated `from_char_string()`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum CharStringError {
+    /// The character at `position` is neither the flag's character nor the
+    /// unset character.
+    InvalidChar {
+        /// Position of the character, counted in `char`s.
+        position: usize,
+    },
+    /// The string does not have one character per flag.
+    WrongLength {
+        /// The number of flags with a display character.
+        expected: usize,
+        /// The number of characters in the string.
+        found: usize,
+    },
+}
+
+impl core::fmt::Display for CharStringError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            CharStringError::InvalidChar { position } => {
+                write!(f, "invalid character at position {}", position)
+            }
+            CharStringError::WrongLength { expected, found } => {
+                write!(f, "expected {} characters, found {}", expected, found)
+            }
+        }
+    }
+}
+
+/// Fixed-capacity string returned by the generated `to_char_string()`.
+///
+/// The capacity `N` is computed from the declared characters, so rendering
+/// never fails and needs no allocation. Dereferences to `str`.
+#[derive(Copy, Clone)]
+pub struct CharString<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> CharString<N> {
+    /// Returns the rendered text.
+    pub fn as_str(&self) -> &str {
+        // Only whole `char`s are written, so this is always valid UTF-8
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+}
+
+impl<const N: usize> core::ops::Deref for CharString<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> AsRef<str> for CharString<N> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> PartialEq for CharString<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<const N: usize> Eq for CharString<N> {}
+
+impl<const N: usize> PartialEq<str> for CharString<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a, const N: usize> PartialEq<&'a str> for CharString<N> {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<const N: usize> core::hash::Hash for CharString<N> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl<const N: usize> core::fmt::Display for CharString<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for CharString<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// Interface shared by all types generated by [`neobit!`].
+///
+/// Generic code such as [`Missing`] is written against this trait. Its
+/// methods are the inherent methods of the same name, which take precedence
+/// in method-call syntax.
+pub trait Flags: Copy + Eq + 'static {
+    /// The underlying integer type.
+    type Bits: Copy + Eq;
+
+    /// The defined flags and their names, in declaration order, aliases
+    /// excluded.
+    const FLAGS: &'static [(&'static str, Self)];
+
+    /// The number of defined flags, the length of `FLAGS`.
+    const FLAG_COUNT: usize = Self::FLAGS.len();
+
+    /// The union of all defined flags, same as `all()`.
+    const ALL: Self;
+
+    /// The name of the underlying integer type, e.g. `"u8"`.
+    const REPR_NAME: &'static str;
+
+    /// The name of the type.
+    const TYPE_NAME: &'static str;
+
+    /// The flags marked `#[w1c]` (write 1 to clear).
+    const W1C_MASK: Self;
+
+    /// The flags marked `#[ro]` (read-only).
+    const RO_MASK: Self;
+
+    /// The flags marked `#[rc]` (cleared by reading).
+    const RC_MASK: Self;
+
+    /// Returns the raw bit value.
+    fn bits(self) -> Self::Bits;
+
+    /// Creates flags from raw bits, keeping unknown bits.
+    fn from_bits_retain(bits: Self::Bits) -> Self;
+
+    /// Returns `true` if all flags in `other` are contained in `self`.
+    fn contains(self, other: Self) -> bool;
+
+    /// The width of `Bits` in bits.
+    const BITS: u32;
+
+    /// Returns the raw bits zero-extended to `u128`.
+    #[doc(hidden)]
+    fn __neobit_to_u128(self) -> u128;
+
+    /// Creates flags from the low `BITS` bits of `bits`.
+    #[doc(hidden)]
+    fn __neobit_from_u128(bits: u128) -> Self;
+
+    /// The `unknown` option of the type.
+    #[doc(hidden)]
+    const __NEOBIT_UNKNOWN: __private::Unknown;
+
+    /// Creates flags from the low `BITS` bits of another flag type's
+    /// `bits`, applying the `unknown` option; `None` if it rejects them.
+    #[doc(hidden)]
+    fn __neobit_from_foreign(bits: u128) -> Option<Self>;
+
+    /// Whether the type converts from `Bits`: `From`, or `TryFrom` with
+    /// `unknown = reject`. `false` with `no_from`.
+    #[doc(hidden)]
+    const __NEOBIT_FROM: bool;
+
+    /// Converts `bits` with the generated `From` or `TryFrom` impl; `None` if
+    /// `TryFrom` rejects them or the type has neither.
+    #[doc(hidden)]
+    fn __neobit_from_bits(bits: Self::Bits) -> Option<Self>;
+
+    /// The `complement` option of the type.
+    #[doc(hidden)]
+    const __NEOBIT_COMPLEMENT: __private::Complement;
+
+    /// Whether the type has the generated `Debug` impl. `false` with
+    /// `debug = "custom"`.
+    #[doc(hidden)]
+    const __NEOBIT_DEBUG: bool;
+
+    /// Writes the flag list of the `Debug` format, e.g. `A | B | 0x80`.
+    #[doc(hidden)]
+    fn __neobit_write_list<E>(self, write: &mut impl FnMut(&str) -> Result<(), E>)
+        -> Result<(), E>;
+
+    /// [`FlagSliceExt::count_containing`] on `Bits`.
+    #[doc(hidden)]
+    fn __neobit_count_containing(values: &[Self], flags: Self) -> usize;
+
+    /// [`FlagSliceExt::count_intersecting`] on `Bits`.
+    #[doc(hidden)]
+    fn __neobit_count_intersecting(values: &[Self], flags: Self) -> usize;
+
+    /// [`FlagSliceExt::insert_all`] on `Bits`.
+    #[doc(hidden)]
+    fn __neobit_insert_all(values: &mut [Self], flags: Self);
+
+    /// [`FlagSliceExt::remove_all`] on `Bits`.
+    #[doc(hidden)]
+    fn __neobit_remove_all(values: &mut [Self], flags: Self);
+}
+
+/// Iterator over the flag names of a value, returned by the generated
+/// `iter_names()`.
+///
+/// Follows bitflags: each defined flag that is contained in the value and
+/// still has an unyielded bit is yielded once, in declaration order.
+#[derive(Clone, Debug)]
+pub struct IterNames<F: 'static> {
+    flags: &'static [(&'static str, F)],
+    idx: usize,
+    source: F,
+    remaining: F,
+}
+
+impl<F: Flags> IterNames<F> {
+    /// Creates an iterator over the flag names of `flags`.
+    pub fn new(flags: F) -> Self {
+        IterNames {
+            flags: F::FLAGS,
+            idx: 0,
+            source: flags,
+            remaining: flags,
+        }
+    }
+
+    /// Returns the bits not yielded so far.
+    pub fn remaining(&self) -> F {
+        self.remaining
+    }
+}
+
+impl<F: Flags> Iterator for IterNames<F> {
+    type Item = (&'static str, F);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(&(name, flag)) = self.flags.get(self.idx) {
+            let remaining = self.remaining.__neobit_to_u128();
+            if remaining == 0 {
+                return None;
+            }
+            self.idx += 1;
+
+            let bits = flag.__neobit_to_u128();
+            if self.source.contains(flag) && remaining & bits != 0 {
+                self.remaining = F::__neobit_from_u128(remaining & !bits);
+                return Some((name, flag));
+            }
+        }
+        None
+    }
+}
+
+/// Iterator over the flags of a value, returned by the generated `iter()`.
+///
+/// Yields the same flags as [`IterNames`], then the bits of no defined flag
+/// as one last value.
+#[derive(Clone, Debug)]
+pub struct Iter<F: 'static> {
+    names: IterNames<F>,
+    done: bool,
+}
+
+impl<F: Flags> Iter<F> {
+    /// Creates an iterator over the flags of `flags`.
+    pub fn new(flags: F) -> Self {
+        Iter {
+            names: IterNames::new(flags),
+            done: false,
+        }
+    }
+}
+
+impl<F: Flags> Iterator for Iter<F> {
+    type Item = F;
+
+    fn next(&mut self) -> Option<F> {
+        if let Some((_, flag)) = self.names.next() {
+            return Some(flag);
+        }
+        if self.done {
+            return None;
+        }
+        self.done = true;
+        let remaining = self.names.remaining();
+        if remaining.__neobit_to_u128() == 0 {
+            None
+        } else {
+            Some(remaining)
+        }
+    }
+}
+
+/// Error returned by the generated `try_narrow()` when set bits do not fit
+/// in the target type, or when the target is declared with
+/// `#[neobit(unknown = reject)]` and does not know them.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct NarrowError<F> {
+    flags: F,
+    target_bits: u32,
+    unknown: bool,
+}
+
+impl<F: Copy> NarrowError<F> {
+    /// Returns the flags that could not be narrowed.
+    pub fn flags(&self) -> F {
+        self.flags
+    }
+
+    /// Returns the width of the target type in bits.
+    pub fn target_bits(&self) -> u32 {
+        self.target_bits
+    }
+
+    /// Returns `true` if the bits fit in the target but are unknown to it
+    /// and rejected by its `unknown = reject` option.
+    pub fn is_unknown(&self) -> bool {
+        self.unknown
+    }
+}
+
+impl<F: Flags> core::fmt::Display for NarrowError<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("flags ")?;
+        self.flags.__neobit_write_list(&mut |s| f.write_str(s))?;
+        if self.unknown {
+            f.write_str(" have bits unknown to the target")
+        } else {
+            write!(f, " do not fit in {} bits", self.target_bits)
+        }
+    }
+}
+
+/// Error returned by `TryFrom<int>` for types declared with
+/// `#[neobit(unknown = reject)]` when bits outside `all()` are set.
+///
+/// ```rust
+/// # use neobit::neobit;
+/// # use core::convert::TryFrom;
+/// # neobit! { #[neobit(unknown = reject)] pub struct Flags: u8 { const A = 1; const B = 2; } }
+/// let err = Flags::try_from(0x81).unwrap_err();
+/// assert_eq!(err.flags(), Flags::from_bits_retain(0x81));
+/// assert_eq!(err.unknown(), 0x80);
+/// assert_eq!(err.to_string(), "unknown bits 0x80 in A | 0x80");
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct UnknownBits<F> {
+    flags: F,
+    known: u128,
+}
+
+impl<F: Flags> UnknownBits<F> {
+    /// Returns the rejected value, unknown bits included.
+    pub fn flags(&self) -> F {
+        self.flags
+    }
+
+    /// Returns the bits of the rejected value outside `all()`.
+    pub fn unknown(&self) -> F::Bits {
+        F::__neobit_from_u128(self.flags.__neobit_to_u128() & !self.known).bits()
+    }
+}
+
+impl<F: Flags> core::fmt::Display for UnknownBits<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("unknown bits ")?;
+        let unknown = self.flags.__neobit_to_u128() & !self.known;
+        __private::write_hex(unknown, F::BITS, &mut |s: &str| f.write_str(s))?;
+        f.write_str(" in ")?;
+        self.flags.__neobit_write_list(&mut |s| f.write_str(s))
+    }
+}
+
+/// Error returned by the generated `require()` and `require_any()`, holding
+/// the flags that were needed but not present.
+///
+/// `Display` lists the missing flags by name:
+///
+/// ```rust
+/// # use neobit::neobit;
+/// # neobit! { pub struct Perms: u8 { const READ = 1; const WRITE = 2; const ADMIN = 4; } }
+/// let err = Perms::READ.require(Perms::READ | Perms::WRITE | Perms::ADMIN).unwrap_err();
+/// assert_eq!(err.flags(), Perms::WRITE | Perms::ADMIN);
+/// assert_eq!(err.to_string(), "missing flags: WRITE | ADMIN");
+///
+/// let err = Perms::READ.require_any(Perms::WRITE | Perms::ADMIN).unwrap_err();
+/// assert_eq!(err.to_string(), "missing any of: WRITE | ADMIN");
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Missing<F> {
+    flags: F,
+    any: bool,
+}
+
+impl<F> Missing<F> {
+    /// Every flag in `flags` was required and is missing.
+    pub const fn all_of(flags: F) -> Self {
+        Missing { flags, any: false }
+    }
+
+    /// Any flag in `flags` would have been sufficient, and none is present.
+    pub const fn any_of(flags: F) -> Self {
+        Missing { flags, any: true }
+    }
+}
+
+impl<F: Copy> Missing<F> {
+    /// Returns the missing flags.
+    pub fn flags(&self) -> F {
+        self.flags
+    }
+
+    /// Returns `true` if only one of the missing flags was required.
+    pub fn is_any_of(&self) -> bool {
+        self.any
+    }
+}
+
+impl<F: Flags> core::fmt::Display for Missing<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(if self.any {
+            "missing any of: "
+        } else {
+            "missing flags: "
+        })?;
+        self.flags.__neobit_write_list(&mut |s| f.write_str(s))
+    }
+}
+
+/// Orders flags by set inclusion instead of by their raw integer value.
+///
+/// The derived `Ord` of generated types compares the bits as integers, which
+/// is useful for `BTreeMap` keys but says nothing about subsets. With this
+/// wrapper `a <= b` means "`a` is a subset of `b`", and two flag sets that
+/// are not subsets of each other are incomparable.
+///
+/// ```rust
+/// # use neobit::{neobit, SubsetOrd};
+/// # neobit! { pub struct Perms: u8 { const READ = 1; const WRITE = 2; const ADMIN = 4; } }
+/// let read = SubsetOrd(Perms::READ);
+/// let read_write = SubsetOrd(Perms::READ | Perms::WRITE);
+/// let admin = SubsetOrd(Perms::ADMIN);
+///
+/// assert!(read < read_write);
+/// assert!(read <= read_write);
+/// assert_eq!(read.partial_cmp(&admin), None); // neither is a subset
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct SubsetOrd<F>(pub F);
+
+impl<F: Flags> PartialOrd for SubsetOrd<F> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        if self.0 == other.0 {
+            Some(core::cmp::Ordering::Equal)
+        } else if other.0.contains(self.0) {
+            Some(core::cmp::Ordering::Less)
+        } else if self.0.contains(other.0) {
+            Some(core::cmp::Ordering::Greater)
+        } else {
+            None
+        }
+    }
+}
+
+impl<F> From<F> for SubsetOrd<F> {
+    fn from(flags: F) -> Self {
+        SubsetOrd(flags)
+    }
+}
+
+/// A match on flag values: some flags must be set, some must be clear, and
+/// the others are ignored.
+///
+/// Patterns are built in `const` context with the generated `pattern()` and
+/// combined with `union()`, so rule tables can be declared statically:
+///
+/// ```rust
+/// # use neobit::{neobit, FlagPattern};
+/// # neobit! { pub struct Tcp: u8 { const FIN = 1; const SYN = 2; const RST = 4; const ACK = 16; } }
+/// const SYN_ONLY: FlagPattern<Tcp> = Tcp::pattern(Tcp::SYN, Tcp::ACK);
+/// const NO_RST: FlagPattern<Tcp> = SYN_ONLY.union(Tcp::pattern(Tcp::empty(), Tcp::RST));
+/// const IS_SYN: bool = Tcp::SYN.satisfies(SYN_ONLY);
+///
+/// assert!(IS_SYN);
+/// assert!(SYN_ONLY.matches(Tcp::SYN | Tcp::FIN));   // FIN is ignored
+/// assert!(!SYN_ONLY.matches(Tcp::SYN | Tcp::ACK));
+/// assert!(!NO_RST.matches(Tcp::SYN | Tcp::RST));
+/// assert_eq!(format!("{:?}", SYN_ONLY), "{+SYN -ACK}");
+/// ```
+///
+/// `must_set()`, `must_clear()` and `matches()` need the [`Flags`] bound,
+/// which a `const fn` cannot have before Rust 1.61, so they are not `const`;
+/// the generated `pattern()` and `satisfies()` are.
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct FlagPattern<F> {
+    // The bits zero-extended to `u128`, like `Flags::__neobit_to_u128`
+    set: u128,
+    clear: u128,
+    flags: core::marker::PhantomData<F>,
+}
+
+impl<F: Flags> FlagPattern<F> {
+    /// Creates a pattern requiring the flags in `must_set` to be set and
+    /// those in `must_clear` to be clear.
+    ///
+    /// In `const` context, use the generated `pattern()` instead.
+    pub fn new(must_set: F, must_clear: F) -> Self {
+        __private::pattern(must_set.__neobit_to_u128(), must_clear.__neobit_to_u128())
+    }
+
+    /// Adds `flags` to the flags that must be set.
+    pub fn must_set(self, flags: F) -> Self {
+        __private::pattern(self.set | flags.__neobit_to_u128(), self.clear)
+    }
+
+    /// Adds `flags` to the flags that must be clear.
+    pub fn must_clear(self, flags: F) -> Self {
+        __private::pattern(self.set, self.clear | flags.__neobit_to_u128())
+    }
+
+    /// Returns `true` if every required flag is set in `value` and every
+    /// forbidden flag is clear.
+    pub fn matches(&self, value: F) -> bool {
+        let value = value.__neobit_to_u128();
+        value & self.set == self.set && value & self.clear == 0
+    }
+
+    /// Returns the flags that must be set.
+    pub fn required(&self) -> F {
+        F::__neobit_from_u128(self.set)
+    }
+
+    /// Returns the flags that must be clear.
+    pub fn forbidden(&self) -> F {
+        F::__neobit_from_u128(self.clear)
+    }
+
+    /// Returns the constrained bits that `value` satisfies: the required
+    /// bits it has set and the forbidden bits it has clear.
+    ///
+    /// ```rust
+    /// # use neobit::{neobit, FlagPattern};
+    /// # neobit! { pub struct Tcp: u8 { const FIN = 1; const SYN = 2; const RST = 4; const ACK = 16; } }
+    /// let pattern = FlagPattern::new(Tcp::SYN | Tcp::ACK, Tcp::RST);
+    /// assert_eq!(pattern.matching_bits(Tcp::SYN | Tcp::RST), Tcp::SYN);
+    /// ```
+    pub fn matching_bits(&self, value: F) -> F {
+        let value = value.__neobit_to_u128();
+        F::__neobit_from_u128((value & self.set) | (!value & self.clear))
+    }
+}
+
+impl<F> FlagPattern<F> {
+    /// Returns a pattern with the constraints of both patterns, matching
+    /// values that both match.
+    pub const fn union(self, other: Self) -> Self {
+        __private::pattern(self.set | other.set, self.clear | other.clear)
+    }
+
+    /// Returns a pattern with the constraints the patterns share, matching
+    /// at least the values that either matches.
+    pub const fn intersection(self, other: Self) -> Self {
+        __private::pattern(self.set & other.set, self.clear & other.clear)
+    }
+
+    /// Returns `false` if a flag is both required and forbidden, so that no
+    /// value matches.
+    pub const fn is_satisfiable(&self) -> bool {
+        self.set & self.clear == 0
+    }
+}
+
+impl<F: Flags> core::fmt::Debug for FlagPattern<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write_set_clear(f, self.required(), self.forbidden())
+    }
+}
+
+/// Writes flags to set and to clear as `{+A +B -C}`.
+fn write_set_clear<F: Flags>(
+    f: &mut core::fmt::Formatter<'_>,
+    set: F,
+    clear: F,
+) -> core::fmt::Result {
+    f.write_str("{")?;
+    let mut first = true;
+    for &(flags, sign) in &[(set, "+"), (clear, "-")] {
+        let mut bits = flags.__neobit_to_u128();
+        for &(name, flag) in F::FLAGS {
+            let value = flag.__neobit_to_u128();
+            if value.is_power_of_two() && bits & value != 0 {
+                if !first {
+                    f.write_str(" ")?;
+                }
+                f.write_str(sign)?;
+                f.write_str(name)?;
+                bits &= !value;
+                first = false;
+            }
+        }
+        if bits != 0 {
+            if !first {
+                f.write_str(" ")?;
+            }
+            f.write_str(sign)?;
+            __private::write_hex(bits, F::BITS, &mut |s| f.write_str(s))?;
+            first = false;
+        }
+    }
+    f.write_str("}")
+}
+
+/// A partial update: the bits in `mask` take their value from `value`, the
+/// others are left as they are.
+///
+/// ```rust
+/// # use neobit::{neobit, Masked};
+/// # neobit! { pub struct Config: u8 { const VERBOSE = 1; const COLOR = 2; const CACHE = 4; } }
+/// // Turn on VERBOSE and turn off COLOR, leave CACHE alone
+/// let update = Masked::from((Config::VERBOSE, Config::COLOR));
+///
+/// assert_eq!(update.apply(Config::COLOR | Config::CACHE), Config::VERBOSE | Config::CACHE);
+/// assert_eq!(format!("{:?}", update), "{+VERBOSE -COLOR}");
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Masked<F> {
+    /// The new values of the bits in `mask`. Other bits are ignored.
+    pub value: F,
+    /// The bits to update.
+    pub mask: F,
+}
+
+impl<F> Masked<F> {
+    /// Creates an update giving the bits in `mask` their value in `value`.
+    pub const fn new(value: F, mask: F) -> Self {
+        Masked { value, mask }
+    }
+}
+
+impl<F: Flags> Masked<F> {
+    /// Creates an update that sets the flags in `set` and clears those in
+    /// `clear`. A flag in both is cleared.
+    pub fn set_clear(set: F, clear: F) -> Self {
+        let clear = clear.__neobit_to_u128();
+        Masked {
+            value: F::__neobit_from_u128(set.__neobit_to_u128() & !clear),
+            mask: F::__neobit_from_u128(set.__neobit_to_u128() | clear),
+        }
+    }
+
+    /// Returns `base` with the masked bits replaced.
+    pub fn apply(self, base: F) -> F {
+        let mask = self.mask.__neobit_to_u128();
+        F::__neobit_from_u128(
+            (base.__neobit_to_u128() & !mask) | (self.value.__neobit_to_u128() & mask),
+        )
+    }
+
+    /// Returns the update applying `self`, then `next`.
+    ///
+    /// ```rust
+    /// # use neobit::{neobit, Masked};
+    /// # neobit! { pub struct Config: u8 { const VERBOSE = 1; const COLOR = 2; const CACHE = 4; } }
+    /// let defaults = Masked::from((Config::COLOR | Config::CACHE, Config::empty()));
+    /// let user = Masked::from((Config::VERBOSE, Config::CACHE));
+    ///
+    /// let layered = defaults.then(user);
+    /// assert_eq!(layered.apply(Config::empty()), Config::VERBOSE | Config::COLOR);
+    /// ```
+    pub fn then(self, next: Self) -> Self {
+        let next_mask = next.mask.__neobit_to_u128();
+        let value = (self.value.__neobit_to_u128() & self.mask.__neobit_to_u128() & !next_mask)
+            | (next.value.__neobit_to_u128() & next_mask);
+        Masked {
+            value: F::__neobit_from_u128(value),
+            mask: F::__neobit_from_u128(self.mask.__neobit_to_u128() | next_mask),
+        }
+    }
+
+    /// Returns the flags the update sets.
+    pub fn to_set(&self) -> F {
+        F::__neobit_from_u128(self.value.__neobit_to_u128() & self.mask.__neobit_to_u128())
+    }
+
+    /// Returns the flags the update clears.
+    pub fn to_clear(&self) -> F {
+        F::__neobit_from_u128(!self.value.__neobit_to_u128() & self.mask.__neobit_to_u128())
+    }
+}
+
+impl<F: Flags> From<(F, F)> for Masked<F> {
+    /// Same as [`Masked::set_clear`].
+    fn from((set, clear): (F, F)) -> Self {
+        Masked::set_clear(set, clear)
+    }
+}
+
+impl<F: Flags> core::fmt::Debug for Masked<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write_set_clear(f, self.to_set(), self.to_clear())
+    }
+}
+
+/// The result of a constant-time operation such as the generated `ct_eq()`:
+/// `1` for true and `0` for false.
+///
+/// Unlike `bool`, a `Choice` is meant to be combined with `&`, `|` and `!`
+/// and passed to `ct_select()` without branching on it. Convert it to `bool`
+/// only once the secret-dependent part is over.
+///
+/// ```rust
+/// # use neobit::neobit;
+/// # neobit! { pub struct Caps: u8 { const READ = 1; const WRITE = 2; const ADMIN = 4; } }
+/// let granted = Caps::READ | Caps::WRITE;
+/// let allowed = granted.ct_contains(Caps::WRITE) & !granted.ct_intersects(Caps::ADMIN);
+/// assert!(bool::from(allowed));
+///
+/// let effective = Caps::ct_select(Caps::empty(), granted, allowed);
+/// assert_eq!(effective, granted);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Choice(u8);
+
+impl Choice {
+    /// Returns `1` for true and `0` for false.
+    #[inline]
+    pub fn unwrap_u8(self) -> u8 {
+        self.0
+    }
+
+    /// Returns `1` if `value` is nonzero, without branching.
+    #[doc(hidden)]
+    #[inline]
+    pub fn __from_nonzero(value: u128) -> Self {
+        let top = (value | value.wrapping_neg()) >> 127;
+        Choice(barrier(top as u8))
+    }
+
+    /// Returns all ones for true and zero for false, passed through the
+    /// barrier before `ct_select()` combines it with the values.
+    #[doc(hidden)]
+    #[inline]
+    pub fn __mask(self) -> u128 {
+        barrier(0u128.wrapping_sub(self.0 as u128))
+    }
+}
+
+/// Hides `value` from the optimizer, so that it cannot turn the masking
+/// arithmetic built on it back into branches.
+#[inline(never)]
+fn barrier<T: Copy>(value: T) -> T {
+    // SAFETY: reading a valid, aligned local
+    unsafe { core::ptr::read_volatile(&value) }
+}
+
+impl From<u8> for Choice {
+    /// Creates a `Choice` from `1` (true) or `0` (false). Only the lowest
+    /// bit is used.
+    fn from(value: u8) -> Self {
+        Choice(barrier(value & 1))
+    }
+}
+
+impl From<Choice> for bool {
+    fn from(choice: Choice) -> bool {
+        choice.0 != 0
+    }
+}
+
+impl core::ops::BitAnd for Choice {
+    type Output = Choice;
+
+    #[inline]
+    fn bitand(self, other: Choice) -> Choice {
+        Choice(self.0 & other.0)
+    }
+}
+
+impl core::ops::BitOr for Choice {
+    type Output = Choice;
+
+    #[inline]
+    fn bitor(self, other: Choice) -> Choice {
+        Choice(self.0 | other.0)
+    }
+}
+
+impl core::ops::Not for Choice {
+    type Output = Choice;
+
+    #[inline]
+    fn not(self) -> Choice {
+        Choice(1 & !self.0)
+    }
+}
+
+/// Bulk operations on slices of flags, such as `Vec<Flags>` columns.
+///
+/// Counting and updating run a loop over the integer type of the flags,
+/// generated with the type, without early exits, so the compiler can
+/// vectorize it. `Vec<Flags>` gets the operations through its slice; for
+/// column-wise storage see `BitPlanes`.
+///
+/// ```rust
+/// # use neobit::{neobit, FlagSliceExt};
+/// # neobit! { pub struct Entity: u8 { const VISIBLE = 1; const DIRTY = 2; const STATIC = 4; } }
+/// let mut entities = vec![Entity::VISIBLE, Entity::VISIBLE | Entity::DIRTY, Entity::STATIC];
+///
+/// assert_eq!(entities.count_containing(Entity::VISIBLE), 2);
+/// let dirty: Vec<usize> = entities.indices_intersecting(Entity::DIRTY | Entity::STATIC).collect();
+/// assert_eq!(dirty, [1, 2]);
+///
+/// entities.remove_all(Entity::DIRTY);
+/// assert_eq!(entities.count_intersecting(Entity::DIRTY), 0);
+/// ```
+pub trait FlagSliceExt<F> {
+    /// Returns the number of values containing all of `flags`.
+    fn count_containing(&self, flags: F) -> usize;
+
+    /// Returns the number of values sharing a bit with `flags`.
+    fn count_intersecting(&self, flags: F) -> usize;
+
+    /// Returns the indices of the values containing all of `flags`.
+    fn indices_containing(&self, flags: F) -> Indices<'_, F>;
+
+    /// Returns the indices of the values sharing a bit with `flags`.
+    fn indices_intersecting(&self, flags: F) -> Indices<'_, F>;
+
+    /// Inserts `flags` into every value.
+    fn insert_all(&mut self, flags: F);
+
+    /// Removes `flags` from every value.
+    fn remove_all(&mut self, flags: F);
+}
+
+impl<F: Flags> FlagSliceExt<F> for [F] {
+    #[inline]
+    fn count_containing(&self, flags: F) -> usize {
+        F::__neobit_count_containing(self, flags)
+    }
+
+    #[inline]
+    fn count_intersecting(&self, flags: F) -> usize {
+        F::__neobit_count_intersecting(self, flags)
+    }
+
+    fn indices_containing(&self, flags: F) -> Indices<'_, F> {
+        Indices::new(self, flags, true)
+    }
+
+    fn indices_intersecting(&self, flags: F) -> Indices<'_, F> {
+        Indices::new(self, flags, false)
+    }
+
+    #[inline]
+    fn insert_all(&mut self, flags: F) {
+        F::__neobit_insert_all(self, flags)
+    }
+
+    #[inline]
+    fn remove_all(&mut self, flags: F) {
+        F::__neobit_remove_all(self, flags)
+    }
+}
+
+/// Iterator over the indices of matching values, returned by
+/// [`FlagSliceExt::indices_containing`] and
+/// [`FlagSliceExt::indices_intersecting`].
+#[derive(Clone, Debug)]
+pub struct Indices<'a, F> {
+    values: core::iter::Enumerate<core::slice::Iter<'a, F>>,
+    flags: u128,
+    all: bool,
+}
+
+impl<'a, F: Flags> Indices<'a, F> {
+    fn new(values: &'a [F], flags: F, all: bool) -> Self {
+        Indices {
+            values: values.iter().enumerate(),
+            flags: flags.__neobit_to_u128(),
+            all,
+        }
+    }
+}
+
+impl<'a, F: Flags> Iterator for Indices<'a, F> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let (flags, all) = (self.flags, self.all);
+        self.values.find_map(|(i, v)| {
+            let common = v.__neobit_to_u128() & flags;
+            let hit = if all { common == flags } else { common != 0 };
+            if hit {
+                Some(i)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Flags stored column-wise, one bit vector per bit of the repr.
+///
+/// A query reads only the columns of the bits it asks about, 64 values per
+/// word, which suits large collections queried for a few sparse flags.
+/// Available with the `alloc` feature.
+///
+/// ```rust
+/// # use neobit::{neobit, BitPlanes};
+/// # neobit! { pub struct Entry: u16 { const DELETED = 1; const PINNED = 2; const SHARED = 4; } }
+/// let mut index: BitPlanes<Entry> = (0..1000)
+///     .map(|i| if i % 100 == 0 { Entry::PINNED } else { Entry::empty() })
+///     .collect();
+///
+/// assert_eq!(index.count_containing(Entry::PINNED), 10);
+/// assert_eq!(index.indices_containing(Entry::PINNED).nth(1), Some(100));
+///
+/// index.insert_all(Entry::SHARED);
+/// assert_eq!(index.get(5), Some(Entry::SHARED));
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct BitPlanes<F> {
+    /// One column per bit, `BITS` in total; bits past `len` are zero.
+    planes: alloc::vec::Vec<alloc::vec::Vec<u64>>,
+    len: usize,
+    _flags: core::marker::PhantomData<F>,
+}
+
+#[cfg(feature = "alloc")]
+impl<F: Flags> BitPlanes<F> {
+    /// Creates an empty collection.
+    pub fn new() -> Self {
+        BitPlanes {
+            planes: (0..F::BITS).map(|_| alloc::vec::Vec::new()).collect(),
+            len: 0,
+            _flags: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends a value.
+    pub fn push(&mut self, value: F) {
+        let (word, bit) = (self.len / 64, self.len % 64);
+        let bits = value.__neobit_to_u128();
+        for (i, plane) in self.planes.iter_mut().enumerate() {
+            if bit == 0 {
+                plane.push(0);
+            }
+            plane[word] |= ((bits >> i) as u64 & 1) << bit;
+        }
+        self.len += 1;
+    }
+
+    /// Returns the value at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<F> {
+        if index >= self.len {
+            return None;
+        }
+        let (word, bit) = (index / 64, index % 64);
+        let bits = self
+            .planes
+            .iter()
+            .enumerate()
+            .fold(0u128, |acc, (i, plane)| {
+                acc | u128::from((plane[word] >> bit) & 1) << i
+            });
+        Some(F::__neobit_from_u128(bits))
+    }
+
+    /// Replaces the value at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, value: F) {
+        assert!(index < self.len, "index {} out of bounds", index);
+        let (word, bit) = (index / 64, index % 64);
+        let bits = value.__neobit_to_u128();
+        for (i, plane) in self.planes.iter_mut().enumerate() {
+            let set = (bits >> i) as u64 & 1;
+            plane[word] = (plane[word] & !(1 << bit)) | set << bit;
+        }
+    }
+
+    /// Returns an iterator over the values.
+    pub fn iter(&self) -> impl Iterator<Item = F> + '_ {
+        (0..self.len).filter_map(move |i| self.get(i))
+    }
+
+    /// Returns the number of values containing all of `flags`.
+    pub fn count_containing(&self, flags: F) -> usize {
+        self.count_words(flags, true)
+    }
+
+    /// Returns the number of values sharing a bit with `flags`.
+    pub fn count_intersecting(&self, flags: F) -> usize {
+        self.count_words(flags, false)
+    }
+
+    /// Returns the indices of the values containing all of `flags`.
+    pub fn indices_containing(&self, flags: F) -> impl Iterator<Item = usize> + '_ {
+        self.indices(flags, true)
+    }
+
+    /// Returns the indices of the values sharing a bit with `flags`.
+    pub fn indices_intersecting(&self, flags: F) -> impl Iterator<Item = usize> + '_ {
+        self.indices(flags, false)
+    }
+
+    /// Inserts `flags` into every value.
+    pub fn insert_all(&mut self, flags: F) {
+        let len = self.len;
+        for plane in self.selected_mut(flags) {
+            for word in plane.iter_mut() {
+                *word = !0;
+            }
+            if len % 64 != 0 {
+                if let Some(last) = plane.last_mut() {
+                    *last = (1 << (len % 64)) - 1;
+                }
+            }
+        }
+    }
+
+    /// Removes `flags` from every value.
+    pub fn remove_all(&mut self, flags: F) {
+        for plane in self.selected_mut(flags) {
+            for word in plane.iter_mut() {
+                *word = 0;
+            }
+        }
+    }
+
+    /// Returns word `word` of the matches for `flags`.
+    fn matches(&self, flags: u128, all: bool, word: usize) -> u64 {
+        let mut acc = if all { self.live_bits(word) } else { 0 };
+        for (i, plane) in self.planes.iter().enumerate() {
+            if flags >> i & 1 != 0 {
+                if all {
+                    acc &= plane[word];
+                } else {
+                    acc |= plane[word];
+                }
+            }
+        }
+        acc
+    }
+
+    /// Returns the mask of the values stored in word `word`.
+    fn live_bits(&self, word: usize) -> u64 {
+        let remaining = self.len - word * 64;
+        if remaining >= 64 {
+            !0
+        } else {
+            (1 << remaining) - 1
+        }
+    }
+
+    fn count_words(&self, flags: F, all: bool) -> usize {
+        let flags = flags.__neobit_to_u128();
+        (0..self.words())
+            .map(|word| self.matches(flags, all, word).count_ones() as usize)
+            .sum()
+    }
+
+    fn indices(&self, flags: F, all: bool) -> impl Iterator<Item = usize> + '_ {
+        let flags = flags.__neobit_to_u128();
+        (0..self.words()).flat_map(move |word| {
+            let mut bits = self.matches(flags, all, word);
+            core::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(word * 64 + bit)
+            })
+        })
+    }
+
+    fn words(&self) -> usize {
+        (self.len + 63) / 64
+    }
+
+    fn selected_mut(&mut self, flags: F) -> impl Iterator<Item = &mut alloc::vec::Vec<u64>> {
+        let flags = flags.__neobit_to_u128();
+        self.planes
+            .iter_mut()
+            .enumerate()
+            .filter(move |(i, _)| flags >> i & 1 != 0)
+            .map(|(_, plane)| plane)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<F: Flags> Default for BitPlanes<F> {
+    fn default() -> Self {
+        BitPlanes::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<F: Flags> core::iter::Extend<F> for BitPlanes<F> {
+    fn extend<I: IntoIterator<Item = F>>(&mut self, values: I) {
+        for value in values {
+            self.push(value);
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<F: Flags> core::iter::FromIterator<F> for BitPlanes<F> {
+    fn from_iter<I: IntoIterator<Item = F>>(values: I) -> Self {
+        let mut planes = BitPlanes::new();
+        planes.extend(values);
+        planes
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, F: Flags> From<&'a [F]> for BitPlanes<F> {
+    fn from(values: &'a [F]) -> Self {
+        values.iter().copied().collect()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<F: Flags> core::fmt::Debug for BitPlanes<F>
+where
+    F: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// The `#[neobit]` attribute, available with the `macros` feature.
+///
+/// It reads an enum (or a struct of `bool` fields) and expands to the
+/// equivalent [`neobit!`] invocation, reporting duplicate names, overlapping
+/// bits and unsupported reprs at the offending token. See the
+/// [`neobit-macros`](https://docs.rs/neobit-macros) documentation for the
+/// accepted syntax.
+#[cfg(feature = "macros")]
+pub mod attr {
+    pub use neobit_macros::neobit;
+}
+
+/// Support code for the macro expansions. Not public API.
+#[doc(hidden)]
+pub mod __private {
+    use crate::{BufferTooSmall, CharString, Flags};
+
+    /// Creates a `FlagPattern` from bits zero-extended to `u128`, for the
+    /// generated `pattern()`.
+    pub const fn pattern<F>(must_set: u128, must_clear: u128) -> crate::FlagPattern<F> {
+        crate::FlagPattern {
+            set: must_set,
+            clear: must_clear,
+            flags: core::marker::PhantomData,
+        }
+    }
+
+    /// The bits of a `FlagPattern` zero-extended to `u128`, for the
+    /// generated `satisfies()`.
+    pub const fn pattern_bits<F>(pattern: &crate::FlagPattern<F>) -> (u128, u128) {
+        (pattern.set, pattern.clear)
+    }
+
+    /// Writes the low `width` bits of `value` as `0x`-prefixed lowercase hex.
+    pub fn write_hex<E>(
+        value: u128,
+        width: u32,
+        write: &mut impl FnMut(&str) -> Result<(), E>,
+    ) -> Result<(), E> {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+        let mut buf = [0u8; 34];
+        buf[0] = b'0';
+        buf[1] = b'x';
+        let mut len = 2;
+
+        let mut shift = width;
+        while shift > 0 {
+            shift -= 4;
+            let nibble = ((value >> shift) & 0xf) as usize;
+            if nibble != 0 || len > 2 || shift == 0 {
+                buf[len] = DIGITS[nibble];
+                len += 1;
+            }
+        }
+
+        match core::str::from_utf8(&buf[..len]) {
+            Ok(s) => write(s),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// The capacity `to_char_string()` needs: the longer of the flag's
+    /// character and the unset character, for every flag.
+    pub const fn char_string_len<T>(chars: &[(T, char)], unset: char) -> usize {
+        let mut len = 0;
+        let mut i = 0;
+        while i < chars.len() {
+            let set = chars[i].1.len_utf8();
+            len += if set > unset.len_utf8() {
+                set
+            } else {
+                unset.len_utf8()
+            };
+            i += 1;
+        }
+        len
+    }
+
+    /// Renders a `CharString` with `write_chars`, which must not write more
+    /// than `N` bytes.
+    pub fn char_string<const N: usize>(
+        write_chars: impl FnOnce(
+            &mut dyn FnMut(&str) -> Result<(), BufferTooSmall>,
+        ) -> Result<(), BufferTooSmall>,
+    ) -> CharString<N> {
+        let mut string = CharString {
+            buf: [0; N],
+            len: 0,
+        };
+        let mut writer = BufWriter::new(&mut string.buf);
+        let _ = write_chars(&mut |s| writer.write_str(s));
+        string.len = writer.len;
+        string
+    }
+
+    // The optional integrations, named through `$crate` so that the crate
+    // invoking `neobit!` needs no dependency of its own.
+    #[cfg(feature = "defmt")]
+    pub use defmt;
+    #[cfg(feature = "subtle")]
+    pub use subtle;
+    #[cfg(feature = "ufmt")]
+    pub use ufmt;
+
+    /// Writes one piece of the `Debug` text to a `defmt` formatter. The
+    /// `defmt` macros name the `defmt` crate, so they are expanded here.
+    #[cfg(feature = "defmt")]
+    pub fn defmt_str(f: defmt::Formatter<'_>, s: &str) {
+        defmt::write!(f, "{=str}", s);
+    }
+
+    /// The `unknown` option: what converting raw bits does with bits
+    /// outside `all()`.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub enum Unknown {
+        Retain,
+        Truncate,
+        Reject,
+    }
+
+    /// The `complement` option: `Raw` masks neither `complement()` nor the
+    /// `!` operator by `all()`, `Known` masks `!` and `Masked` both.
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub enum Complement {
+        Raw,
+        Known,
+        Masked,
+    }
+
+    /// Compile-time checks for the conversions between flag types. Using
+    /// `SAME`, `NOT_NARROWER` or `INFALLIBLE` fails the build when they do
+    /// not hold.
+    pub struct Widths<A, B>(core::marker::PhantomData<(A, B)>);
+
+    impl<A: Flags, B: Flags> Widths<A, B> {
+        pub const SAME: () = [()][(A::BITS != B::BITS) as usize];
+        pub const NOT_NARROWER: () = [()][(A::BITS > B::BITS) as usize];
+        /// `B` does not reject unknown bits.
+        pub const INFALLIBLE: () =
+            [()][(B::__NEOBIT_UNKNOWN as usize == Unknown::Reject as usize) as usize];
+    }
+
+    /// Builds the error of `try_narrow()` into `F`.
+    pub fn narrow_error<T: Flags, F: Flags>(flags: T) -> crate::NarrowError<T> {
+        crate::NarrowError {
+            flags,
+            target_bits: F::BITS,
+            unknown: matches!(
+                flags.__neobit_to_u128().checked_shr(F::BITS),
+                Some(0) | None
+            ),
+        }
+    }
+
+    /// Builds the error of a rejecting `TryFrom<int>`.
+    pub fn unknown_bits<F: Flags>(flags: F, all: F) -> crate::UnknownBits<F> {
+        crate::UnknownBits {
+            flags,
+            known: all.__neobit_to_u128(),
+        }
+    }
+
+    /// Converts the bits of `flags` into `F`, zero-extending or truncating
+    /// the width and applying the `unknown` option of `F`. Only called once
+    /// `Widths::INFALLIBLE` holds.
+    pub fn convert<T: Flags, F: Flags>(flags: T) -> F {
+        let bits = flags.__neobit_to_u128();
+        F::__neobit_from_foreign(bits).unwrap_or_else(|| F::__neobit_from_u128(bits))
+    }
+
+    /// Narrows `flags` into `F`, or returns `None` if set bits do not fit or
+    /// `F` rejects them as unknown.
+    pub fn try_convert<T: Flags, F: Flags>(flags: T) -> Option<F> {
+        match flags.__neobit_to_u128().checked_shr(F::BITS) {
+            Some(0) | None => F::__neobit_from_foreign(flags.__neobit_to_u128()),
+            Some(_) => None,
+        }
+    }
+
+    /// Collects text into a caller-provided buffer.
+    pub struct BufWriter<'a> {
+        buf: &'a mut [u8],
+        len: usize,
+    }
+
+    impl<'a> BufWriter<'a> {
+        pub fn new(buf: &'a mut [u8]) -> Self {
+            BufWriter { buf, len: 0 }
+        }
+
+        pub fn write_str(&mut self, s: &str) -> Result<(), BufferTooSmall> {
+            let end = self.len + s.len();
+            if end > self.buf.len() {
+                return Err(BufferTooSmall);
+            }
+            self.buf[self.len..end].copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+
+        pub fn finish(self) -> &'a str {
+            let buf: &'a [u8] = self.buf;
+            // Only whole `str`s were copied in, so this is always valid UTF-8
+            core::str::from_utf8(&buf[..self.len]).unwrap_or("")
+        }
+    }
+
+    impl<'a> core::fmt::Write for BufWriter<'a> {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            BufWriter::write_str(self, s).map_err(|_| core::fmt::Error)
+        }
+    }
+
+    /// Values exercised by `neobit_test_suite!`: edge bit patterns, every
+    /// single bit, every defined flag and their union.
+    pub fn suite_samples<F: Flags>() -> impl Iterator<Item = F> + Clone {
+        let top = 1u128 << (F::BITS - 1);
+        let all = F::FLAGS
+            .iter()
+            .fold(0, |acc, &(_, flag)| acc | flag.__neobit_to_u128());
+        let alternating = 0x5555_5555_5555_5555_5555_5555_5555_5555;
+        [0, !0, alternating, !alternating, all, all | top]
+            .into_iter()
+            .chain((0..F::BITS).map(|bit| 1u128 << bit))
+            .chain(F::FLAGS.iter().map(|&(_, flag)| flag.__neobit_to_u128()))
+            .map(F::__neobit_from_u128)
+    }
+
+    /// Hashes `value` with FNV-1a, for the `Hash` checks of
+    /// `neobit_test_suite!`.
+    pub fn suite_hash<T: core::hash::Hash>(value: &T) -> u64 {
+        struct Fnv(u64);
+
+        impl core::hash::Hasher for Fnv {
+            fn finish(&self) -> u64 {
+                self.0
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                for &byte in bytes {
+                    self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+                }
+            }
+        }
+
+        let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+        value.hash(&mut hasher);
+        core::hash::Hasher::finish(&hasher)
+    }
+}
+
 /// Defines a bitflags struct with the specified flags.
 ///
 /// # Example
@@ -82,30 +1497,1422 @@
 /// let all = Flags::all();
 /// assert!(all.contains(flags));
 /// ```
+///
+/// # Aliases
+///
+/// `alias NAME = FLAG;` declares a second name for an existing flag. Aliases
+/// are regular constants and are accepted by `from_name`, but
+/// `Debug` and `all()` only see the canonical flag. Attributes such as
+/// `#[deprecated]` are forwarded to the alias constant.
+///
+/// ```rust
+/// use neobit::neobit;
+///
+/// neobit! {
+///     pub struct OpenFlags: u32 {
+///         const SYNC = 0x0010;
+///         alias FSYNC = SYNC;
+///         #[deprecated(note = "use `SYNC`")]
+///         alias O_SYNC = SYNC;
+///     }
+/// }
+///
+/// assert_eq!(OpenFlags::FSYNC, OpenFlags::SYNC);
+/// assert_eq!(OpenFlags::from_name("FSYNC"), Some(OpenFlags::SYNC));
+/// assert_eq!(format!("{:?}", OpenFlags::FSYNC), "OpenFlags(SYNC)");
+/// ```
+///
+/// # Bit Positions
+///
+/// In flag values, `bit(n)` is the mask of bit `n`, counted from the least
+/// significant bit. With `#[neobit(msb0)]` it counts from the most
+/// significant bit of the repr instead, as in IEEE and PowerPC datasheets.
+/// A position outside the repr fails to compile. Inside flag values `bit`
+/// always refers to this helper.
+///
+/// ```rust
+/// use neobit::neobit;
+///
+/// neobit! {
+///     #[neobit(msb0)]
+///     pub struct MachineState: u32 {
+///         const SF = bit(0);
+///         const LE = bit(31);
+///     }
+/// }
+///
+/// assert_eq!(MachineState::SF.bits(), 0x8000_0000);
+/// assert_eq!(MachineState::LE.bits(), 0x0000_0001);
+/// ```
+///
+/// # Visibility and Hidden Flags
+///
+/// Flag constants are `pub` unless the declaration starts with a visibility,
+/// e.g. `pub(crate) const BUSY = 0x80;`. Unlike other Rust items, a flag
+/// without a visibility is public: write `pub(self)` for a private flag.
+/// Flags that are not `pub` are left out of the `consts` module.
+///
+/// A `#[hidden]` flag only gets its constant: it is not part of `all()`,
+/// `FLAGS`, the companion enum, `from_name()` or the character codes. Its
+/// bits are unknown bits, so `Debug` shows them as a hex remainder and
+/// `from_bits()` rejects them.
+///
+/// Each flag with attributes other than doc comments adds a step of macro
+/// recursion, so a type with more than about a hundred of them may need a
+/// higher `#![recursion_limit]`.
+///
+/// ```rust
+/// use neobit::neobit;
+///
+/// neobit! {
+///     pub struct Status: u8 {
+///         const READY = 0x01;
+///         const ERROR = 0x02;
+///         /// Driver bookkeeping, never reported.
+///         #[hidden]
+///         pub(crate) const BUSY = 0x80;
+///     }
+/// }
+///
+/// assert_eq!(Status::all().bits(), 0x03);
+/// assert_eq!(Status::from_name("BUSY"), None);
+/// assert_eq!(format!("{:?}", Status::READY | Status::BUSY), "Status(READY | 0x80)");
+/// ```
+///
+/// A `pub(self)` flag is only reachable from the module of the type:
+///
+/// ```rust,compile_fail,E0624
+/// mod driver {
+///     neobit::neobit! {
+///         pub struct Status: u8 {
+///             const READY = 0x01;
+///             pub(self) const TEST_HOOK = 0x40;
+///         }
+///     }
+/// }
+///
+/// let hook = driver::Status::TEST_HOOK;
+/// ```
+///
+/// # Register Access
+///
+/// Flags of a hardware register can be marked with their access kind:
+/// `#[w1c]` (write 1 to clear), `#[ro]` (read-only) or `#[rc]` (cleared by
+/// reading). The markers are collected into the `W1C_MASK`, `RO_MASK` and
+/// `RC_MASK` constants of [`Flags`] and are not emitted.
+///
+/// Writing a read value back clears every pending `#[w1c]` flag. Use
+/// `modify_safe()` for read-modify-write, which also leaves out the
+/// `#[ro]` flags, and `write_value_to_clear()` to clear specific flags.
+/// `after_read()` models the `#[rc]` flags being cleared by the read, and
+/// `read_has_side_effects()` tells whether there are any:
+///
+/// ```rust
+/// use neobit::neobit;
+///
+/// neobit! {
+///     pub struct UartStatus: u32 {
+///         const TXEIE = 1 << 0;
+///         const RXNEIE = 1 << 1;
+///         #[ro]
+///         const BUSY = 1 << 4;
+///         #[w1c]
+///         const ORE = 1 << 8;
+///         #[w1c]
+///         const FE = 1 << 9;
+///         #[rc]
+///         const IDLE = 1 << 12;
+///     }
+/// }
+///
+/// let read = UartStatus::TXEIE | UartStatus::BUSY | UartStatus::ORE | UartStatus::FE;
+/// let write = read.modify_safe(|r| r.insert(UartStatus::RXNEIE));
+/// assert_eq!(write, UartStatus::TXEIE | UartStatus::RXNEIE);
+///
+/// let write = write | UartStatus::write_value_to_clear(UartStatus::ORE);
+/// assert_eq!(write.bits(), 0x103);
+///
+/// assert!(UartStatus::read_has_side_effects());
+/// assert_eq!((UartStatus::IDLE | UartStatus::BUSY).after_read(), UartStatus::BUSY);
+/// ```
+///
+/// # Character Codes
+///
+/// A flag can declare a display character with `=> 'c'`. The flags with a
+/// character get a fixed-position rendering such as `rwxr-x---`:
+/// `to_char_string()`, its fixed-buffer variant `write_char_string()`, and
+/// the parser `from_char_string()`. Unset flags render as `-`, or as the
+/// character set with `#[neobit(unset_char = '.')]`.
+///
+/// ```rust
+/// use neobit::neobit;
+///
+/// neobit! {
+///     pub struct Mode: u16 {
+///         const OWNER_READ  = 0o400 => 'r';
+///         const OWNER_WRITE = 0o200 => 'w';
+///         const OWNER_EXEC  = 0o100 => 'x';
+///         const GROUP_READ  = 0o040 => 'r';
+///         const GROUP_WRITE = 0o020 => 'w';
+///         const GROUP_EXEC  = 0o010 => 'x';
+///         const OTHER_READ  = 0o004 => 'r';
+///         const OTHER_WRITE = 0o002 => 'w';
+///         const OTHER_EXEC  = 0o001 => 'x';
+///     }
+/// }
+///
+/// assert_eq!(Mode::from(0o750).to_char_string(), "rwxr-x---");
+/// assert_eq!(Mode::from_char_string("rw-r--r--"), Ok(Mode::from(0o644)));
+/// ```
+///
+/// # Options
+///
+/// A `#[neobit(...)]` attribute on the struct configures the expansion. It
+/// may appear anywhere among the struct attributes and is not emitted.
+///
+/// - `complement = raw | known | masked`: semantics of the `!` operator. `raw`
+///   (the default) is `complement()`, a pure bitwise NOT; `known` is
+///   `complement_known()`, masked by `all()` like bitflags. `masked` also
+///   masks `complement()` itself, for code migrated from bitflags.
+/// - `flag_enum = Name { Variant, ... }`: also generate `enum Name` with the
+///   given variants, one per flag (aliases excluded) in declaration order, so
+///   they can follow the usual UpperCamelCase naming; `#[neobit]` derives them
+///   from the flag names. It comes with `ALL_VARIANTS`,
+///   `From<Name>` for the flags type, `TryFrom<Flags>` for single-flag values
+///   and `Flags::iter_variants()`.
+/// - `unset_char = 'c'`: the character `to_char_string()` renders for unset
+///   flags (default `'-'`).
+/// - `consts = name`: also generate `mod name` with every flag and alias as a
+///   plain integer constant, for binding generators such as cbindgen. Flag
+///   values are copied as written, so they must not refer to `Self`. The
+///   struct is made `#[repr(transparent)]` and its size and alignment are
+///   checked against the repr at compile time.
+/// - `no_from`: do not implement `From<int>` for the flags type, so raw bits
+///   need an explicit `from_bits*()` call. `From<Flags>` for the integer is
+///   kept.
+/// - `unknown = retain | truncate | reject`: how `From<int>` and the
+///   conversions from other flag types (`cast()`, `widen()`, `try_narrow()`)
+///   treat bits outside `all()`. `retain` (the default) keeps them, like
+///   `from_bits_retain()`; `truncate` drops them, like
+///   `from_bits_truncate()`; `reject` implements `TryFrom<int>` instead,
+///   failing with [`UnknownBits`], and only accepts `try_narrow()` from
+///   other flag types. `TryFrom<int>` exists under every policy, so
+///   deserializers and parsers of raw values built on it follow the type's
+///   policy. With `no_from` neither `From<int>` nor `TryFrom<int>` is
+///   implemented.
+/// - `no_ord`: do not implement `Ord` and `PartialOrd`, e.g. to provide an
+///   order of your own.
+/// - `debug = "names" | "custom"`: `names` (the default) implements `Debug`
+///   as `Flags(A | B)`; `custom` leaves `Debug` to you. `write_names()` is
+///   available either way.
+/// - `no_metadata`: do not define the [`Flags`] constants `FLAGS`,
+///   `FLAG_COUNT`, `ALL`, `REPR_NAME` and `TYPE_NAME` as inherent constants,
+///   e.g. to keep a flag or constant of the same name. They stay available
+///   through the trait.
+/// - `msb0`: count `bit(n)` positions from the most significant bit.
+/// - `zero = NAME`: the flag shown by `Debug` (and the `Display` of the
+///   errors) for the empty value, e.g. `Flags(NONE)` instead of
+///   `Flags(empty)`. The flag must be zero, which is checked at compile time;
+///   `from_name()` returns the empty value for it like for any flag. Note
+///   that every value `contains()` it.
+///
+/// ```rust
+/// use neobit::neobit;
+///
+/// neobit! {
+///     /// A pure logical set: `!` never produces unknown bits.
+///     #[neobit(complement = known)]
+///     pub struct Permissions: i8 {
+///         const READ    = 0b001;
+///         const WRITE   = 0b010;
+///         const EXECUTE = 0b100;
+///     }
+/// }
+///
+/// assert_eq!(!Permissions::READ, Permissions::WRITE | Permissions::EXECUTE);
+/// assert_eq!(Permissions::READ.complement().bits(), -2);
+/// ```
+///
+/// ```rust
+/// use neobit::neobit;
+///
+/// neobit! {
+///     #[neobit(flag_enum = PermissionsFlag { Read, Write, Execute })]
+///     pub struct Permissions: u8 {
+///         const READ    = 0b001;
+///         const WRITE   = 0b010;
+///         const EXECUTE = 0b100;
+///     }
+/// }
+///
+/// fn label(flag: PermissionsFlag) -> &'static str {
+///     match flag {
+///         PermissionsFlag::Read => "Read",
+///         PermissionsFlag::Write => "Write",
+///         PermissionsFlag::Execute => "Execute",
+///     }
+/// }
+///
+/// let perms = Permissions::READ | Permissions::EXECUTE;
+/// let labels: Vec<_> = perms.iter_variants().map(label).collect();
+/// assert_eq!(labels, ["Read", "Execute"]);
+/// assert_eq!(Permissions::from(PermissionsFlag::Write), Permissions::WRITE);
+/// assert_eq!(PermissionsFlag::Write.name(), "WRITE");
+/// ```
+///
+/// ```rust
+/// use neobit::neobit;
+///
+/// neobit! {
+///     #[neobit(consts = permissions_consts)]
+///     pub struct Permissions: u8 {
+///         const READ    = 0b001;
+///         const WRITE   = 0b010;
+///         const EXECUTE = 0b100;
+///     }
+/// }
+///
+/// assert_eq!(permissions_consts::WRITE, 0b010u8);
+/// assert_eq!(core::mem::size_of::<Permissions>(), 1);
+/// ```
+///
+/// ```rust
+/// use core::convert::TryFrom;
+/// use neobit::neobit;
+///
+/// neobit! {
+///     /// Read from the network: unknown bits are an error.
+///     #[neobit(unknown = reject)]
+///     pub struct Capabilities: u16 {
+///         const COMPRESSION = 0b01;
+///         const ENCRYPTION  = 0b10;
+///     }
+/// }
+///
+/// assert_eq!(Capabilities::try_from(0b11), Ok(Capabilities::all()));
+/// assert_eq!(Capabilities::try_from(0x100).unwrap_err().unknown(), 0x100);
+/// ```
+///
+/// ```rust
+/// use neobit::neobit;
+///
+/// neobit! {
+///     #[neobit(no_from, debug = "custom")]
+///     pub struct Interlocks: u8 {
+///         const DOOR_CLOSED = 0b01;
+///         const ESTOP_CLEAR = 0b10;
+///     }
+/// }
+///
+/// impl core::fmt::Debug for Interlocks {
+///     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+///         write!(f, "Interlocks({:#04b})", self.bits())
+///     }
+/// }
+///
+/// let raw = 0b11;
+/// let interlocks = Interlocks::from_bits(raw).expect("unknown interlock bits");
+/// assert_eq!(format!("{:?}", interlocks), "Interlocks(0b11)");
+/// ```
 #[macro_export]
 macro_rules! neobit {
     (
+        $(#[$($meta:tt)*])*
+        $vis:vis struct $name:ident: $int_ty:ty {
+            $(
+                $(#[$($flag_meta:tt)*])*
+                $flag_first:ident $(($($flag_vis_args:tt)*))? $flag_second:ident $($flag_third:ident)?
+                    = $flag_value:expr $(=> $flag_char:literal)?;
+            )*
+        }
+    ) => {
+        $crate::__neobit_impl! {
+            @parse {
+                flags: [],
+                names: [],
+                canonical: [],
+                parse: [],
+                chars: [],
+                known: []
+            }
+            [$({
+                [pub] [] [$(#[$($flag_meta)*])*]
+                $flag_first $(($($flag_vis_args)*))? $flag_second $($flag_third)?
+                    = $flag_value $(=> $flag_char)?
+            })*]
+            @attrs {
+                complement: raw,
+                flag_enum: [],
+                consts: [],
+                unset_char: '-',
+                numbering: lsb0,
+                zero: [],
+                impls: { from: true, unknown: retain, ord: true, debug: names, metadata: true },
+                transparent: false
+            }
+            []
+            $(#[$($meta)*])*
+            $vis struct $name: $int_ty;
+        }
+    };
+}
+
+/// Defines flags with the syntax of bitflags 2, for migrating type by type.
+///
+/// Requires the `bitflags-compat` feature. Replacing `bitflags::bitflags!`
+/// with `neobit::bitflags!` keeps the declarations as they are:
+///
+/// - `const _ = !0;` (or any value) marks bits as known without naming them.
+///   They are part of `all()`, so `from_bits()` and `from_bits_truncate()`
+///   accept them, but `iter_names()` and `Debug` do not show them.
+/// - Derives of traits that neobit implements itself (`Debug`, `Clone`,
+///   `Copy`, `PartialEq`, `Eq`, `PartialOrd`, `Ord`, `Hash`, `Default`) are
+///   dropped, other derives are kept.
+/// - The type uses `#[neobit(complement = masked)]`: `complement()` and `!`
+///   are masked by `all()` as in bitflags.
+///
+/// `iter()`, `iter_names()`, `from_name()` and `bits()` behave as in
+/// bitflags. Remaining differences: `is_all()` requires the exact bits of
+/// `all()` (bitflags: contains `all()`, see `is_all_known()`), and the
+/// `impl Name: Bits { ... }` form for external types is not supported.
+///
+/// # Example
+///
+/// ```rust
+/// neobit::bitflags! {
+///     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+///     pub struct Flags: u8 {
+///         const A = 0b0001;
+///         const B = 0b0010;
+///         const AB = Self::A.bits() | Self::B.bits();
+///         const _ = !0;
+///     }
+/// }
+///
+/// assert_eq!(Flags::all().bits(), 0xFF);
+/// assert_eq!(!Flags::A, Flags::from_bits_retain(0xFE));
+/// let names: Vec<_> = (Flags::A | Flags::B).iter_names().map(|(n, _)| n).collect();
+/// assert_eq!(names, ["A", "B"]);
+/// ```
+#[cfg(feature = "bitflags-compat")]
+#[macro_export]
+macro_rules! bitflags {
+    (
+        $(#[$($outer:tt)*])*
+        $vis:vis struct $name:ident: $int_ty:ty {
+            $(
+                $(#[$($inner:tt)*])*
+                const $flag:tt = $value:expr;
+            )*
+        }
+
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @derives [] [$(#[$($outer)*])*]
+            {
+                @parse {
+                    flags: [],
+                    names: [],
+                    canonical: [],
+                    parse: [],
+                    chars: [],
+                    known: []
+                }
+                [$({ [pub] [] [$(#[$($inner)*])*] const $flag = $value })*]
+                @attrs {
+                    complement: masked,
+                    flag_enum: [],
+                    consts: [],
+                    unset_char: '-',
+                    numbering: lsb0,
+                    zero: [],
+                    impls: { from: true, unknown: retain, ord: true, debug: names, metadata: true },
+                    transparent: false
+                }
+                []
+            }
+            $vis struct $name: $int_ty;
+        }
+
+        $crate::bitflags! {
+            $($rest)*
+        }
+    };
+    () => {};
+}
+
+/// Generates the struct and impls for [`neobit!`].
+///
+/// The flag declarations are normalized first (`@parse`): the visibility is
+/// made explicit, `#[hidden]` is removed, and the names are sorted into the
+/// lists the expansion needs. Plain documented flags are handled several at a
+/// time to keep the recursion depth low for large flag sets. Struct
+/// attributes are scanned next (`@attrs`): `#[neobit(...)]` is parsed into the
+/// options record (`@options`), everything else is kept and emitted on the
+/// struct (`@emit`).
+///
+/// The normalized flags are `{ [vis] [attrs] kind NAME = value }`. The lists
+/// hold every declared name (`names`, to resolve alias targets), the listed
+/// flags (`canonical`), the names accepted by `from_name` (`parse`), the
+/// listed flags with a display character (`chars`), and the values of
+/// unnamed `const _` flags, which only add to `all()` (`known`).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_impl {
+    (
+        @parse {
+            flags: [$($flags:tt)*],
+            names: [$($names:ident)*],
+            canonical: [$($canonical:ident)*],
+            parse: [$($parse:ident)*],
+            chars: [$($chars:tt)*],
+            known: $known:tt
+        }
+        [
+            { [pub] [] [$(#[doc = $a_doc:literal])*] const $a:ident = $a_value:expr $(=> $a_char:literal)? }
+            { [pub] [] [$(#[doc = $b_doc:literal])*] const $b:ident = $b_value:expr $(=> $b_char:literal)? }
+            { [pub] [] [$(#[doc = $c_doc:literal])*] const $c:ident = $c_value:expr $(=> $c_char:literal)? }
+            { [pub] [] [$(#[doc = $d_doc:literal])*] const $d:ident = $d_value:expr $(=> $d_char:literal)? }
+            $($entries:tt)*
+        ]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @parse {
+                flags: [
+                    $($flags)*
+                    { [pub] [$(#[doc = $a_doc])*] const $a = $a_value }
+                    { [pub] [$(#[doc = $b_doc])*] const $b = $b_value }
+                    { [pub] [$(#[doc = $c_doc])*] const $c = $c_value }
+                    { [pub] [$(#[doc = $d_doc])*] const $d = $d_value }
+                ],
+                names: [$($names)* $a $b $c $d],
+                canonical: [$($canonical)* $a $b $c $d],
+                parse: [$($parse)* $a $b $c $d],
+                chars: [$($chars)* $($a $a_char)? $($b $b_char)? $($c $c_char)? $($d $d_char)?],
+                known: $known
+            }
+            [$($entries)*]
+            $($rest)*
+        }
+    };
+    (
+        @parse {
+            flags: [$($flags:tt)*],
+            names: [$($names:ident)*],
+            canonical: [$($canonical:ident)*],
+            parse: [$($parse:ident)*],
+            chars: [$($chars:tt)*],
+            known: $known:tt
+        }
+        [
+            {
+                [pub] [] [$(#[doc = $doc:literal])*]
+                const $flag_name:ident = $flag_value:expr $(=> $flag_char:literal)?
+            }
+            $($entries:tt)*
+        ]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @parse {
+                flags: [$($flags)* { [pub] [$(#[doc = $doc])*] const $flag_name = $flag_value }],
+                names: [$($names)* $flag_name],
+                canonical: [$($canonical)* $flag_name],
+                parse: [$($parse)* $flag_name],
+                chars: [$($chars)* $($flag_name $flag_char)?],
+                known: $known
+            }
+            [$($entries)*]
+            $($rest)*
+        }
+    };
+
+    // `const _ = bits;` (from `bitflags!`) adds known bits without a name.
+    (
+        @parse {
+            flags: $flags:tt,
+            names: $names:tt,
+            canonical: $canonical:tt,
+            parse: $parse:tt,
+            chars: $chars:tt,
+            known: [$($known:tt)*]
+        }
+        [{ $flag_vis:tt $kept:tt $attrs:tt const _ = $flag_value:expr } $($entries:tt)*]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @parse {
+                flags: $flags,
+                names: $names,
+                canonical: $canonical,
+                parse: $parse,
+                chars: $chars,
+                known: [$($known)* ($flag_value)]
+            }
+            [$($entries)*]
+            $($rest)*
+        }
+    };
+
+    // `#[hidden]` flags only get their constant. The marker is looked for
+    // among the next four attributes, the others are kept.
+    (
+        @parse {
+            flags: [$($flags:tt)*],
+            names: [$($names:ident)*],
+            canonical: $canonical:tt,
+            parse: $parse:tt,
+            chars: $chars:tt,
+            known: $known:tt
+        }
+        [
+            {
+                [$($flag_vis:tt)*] [$($kept:tt)*] [#[hidden] $($attrs:tt)*]
+                $flag_kind:ident $flag_name:ident = $flag_value:expr $(=> $flag_char:literal)?
+            }
+            $($entries:tt)*
+        ]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @parse {
+                flags: [
+                    $($flags)*
+                    { [$($flag_vis)*] [$($kept)* $($attrs)*] $flag_kind $flag_name = $flag_value }
+                ],
+                names: [$($names)* $flag_name],
+                canonical: $canonical,
+                parse: $parse,
+                chars: $chars,
+                known: $known
+            }
+            [$($entries)*]
+            $($rest)*
+        }
+    };
+    (
+        @parse $state:tt
+        [
+            {
+                $flag_vis:tt [$($kept:tt)*] [#[$($a:tt)*] #[hidden] $($attrs:tt)*]
+                $($flag:tt)*
+            }
+            $($entries:tt)*
+        ]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @parse $state
+            [{ $flag_vis [$($kept)* #[$($a)*]] [#[hidden] $($attrs)*] $($flag)* } $($entries)*]
+            $($rest)*
+        }
+    };
+    (
+        @parse $state:tt
+        [
+            {
+                $flag_vis:tt [$($kept:tt)*] [#[$($a:tt)*] #[$($b:tt)*] #[hidden] $($attrs:tt)*]
+                $($flag:tt)*
+            }
+            $($entries:tt)*
+        ]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @parse $state
+            [{ $flag_vis [$($kept)* #[$($a)*] #[$($b)*]] [#[hidden] $($attrs)*] $($flag)* } $($entries)*]
+            $($rest)*
+        }
+    };
+    (
+        @parse $state:tt
+        [
+            {
+                $flag_vis:tt [$($kept:tt)*] [#[$($a:tt)*] #[$($b:tt)*] #[$($c:tt)*] #[hidden] $($attrs:tt)*]
+                $($flag:tt)*
+            }
+            $($entries:tt)*
+        ]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @parse $state
+            [{ $flag_vis [$($kept)* #[$($a)*] #[$($b)*] #[$($c)*]] [#[hidden] $($attrs)*] $($flag)* } $($entries)*]
+            $($rest)*
+        }
+    };
+    (
+        @parse $state:tt
+        [
+            {
+                $flag_vis:tt [$($kept:tt)*] [#[$($a:tt)*] #[$($b:tt)*] #[$($c:tt)*] #[$($d:tt)*] #[$($e:tt)*] $($attrs:tt)*]
+                $($flag:tt)*
+            }
+            $($entries:tt)*
+        ]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @parse $state
+            [{ $flag_vis [$($kept)* #[$($a)*] #[$($b)*] #[$($c)*] #[$($d)*]] [#[$($e)*] $($attrs)*] $($flag)* } $($entries)*]
+            $($rest)*
+        }
+    };
+
+    // No `#[hidden]`: the flag is listed.
+    (
+        @parse {
+            flags: [$($flags:tt)*],
+            names: [$($names:ident)*],
+            canonical: [$($canonical:ident)*],
+            parse: [$($parse:ident)*],
+            chars: [$($chars:tt)*],
+            known: $known:tt
+        }
+        [
+            {
+                [$($flag_vis:tt)*] [$($kept:tt)*] [$($attrs:tt)*]
+                const $flag_name:ident = $flag_value:expr $(=> $flag_char:literal)?
+            }
+            $($entries:tt)*
+        ]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @parse {
+                flags: [
+                    $($flags)*
+                    { [$($flag_vis)*] [$($kept)* $($attrs)*] const $flag_name = $flag_value }
+                ],
+                names: [$($names)* $flag_name],
+                canonical: [$($canonical)* $flag_name],
+                parse: [$($parse)* $flag_name],
+                chars: [$($chars)* $($flag_name $flag_char)?],
+                known: $known
+            }
+            [$($entries)*]
+            $($rest)*
+        }
+    };
+    (
+        @parse {
+            flags: [$($flags:tt)*],
+            names: [$($names:ident)*],
+            canonical: $canonical:tt,
+            parse: [$($parse:ident)*],
+            chars: [$($chars:tt)*],
+            known: $known:tt
+        }
+        [
+            {
+                [$($flag_vis:tt)*] [$($kept:tt)*] [$($attrs:tt)*]
+                alias $flag_name:ident = $flag_value:expr $(=> $flag_char:literal)?
+            }
+            $($entries:tt)*
+        ]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @parse {
+                flags: [
+                    $($flags)*
+                    { [$($flag_vis)*] [$($kept)* $($attrs)*] alias $flag_name = $flag_value }
+                ],
+                names: [$($names)* $flag_name],
+                canonical: $canonical,
+                parse: [$($parse)* $flag_name],
+                chars: [$($chars)* $($flag_name $flag_char)?],
+                known: $known
+            }
+            [$($entries)*]
+            $($rest)*
+        }
+    };
+    // Unknown kinds are reported by `__neobit_flag!`.
+    (
+        @parse {
+            flags: [$($flags:tt)*],
+            names: [$($names:ident)*],
+            canonical: $canonical:tt,
+            parse: $parse:tt,
+            chars: $chars:tt,
+            known: $known:tt
+        }
+        [
+            {
+                [$($flag_vis:tt)*] [$($kept:tt)*] [$($attrs:tt)*]
+                $flag_kind:ident $flag_name:ident = $flag_value:expr $(=> $flag_char:literal)?
+            }
+            $($entries:tt)*
+        ]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @parse {
+                flags: [
+                    $($flags)*
+                    { [$($flag_vis)*] [$($kept)* $($attrs)*] $flag_kind $flag_name = $flag_value }
+                ],
+                names: [$($names)* $flag_name],
+                canonical: $canonical,
+                parse: $parse,
+                chars: $chars,
+                known: $known
+            }
+            [$($entries)*]
+            $($rest)*
+        }
+    };
+
+    // Declarations are public unless they start with a visibility.
+    (
+        @parse $state:tt
+        [
+            {
+                [pub] $kept:tt $attrs:tt
+                pub $(($($flag_vis_args:tt)*))? $flag_kind:ident $flag_name:ident
+                    = $flag_value:expr $(=> $flag_char:literal)?
+            }
+            $($entries:tt)*
+        ]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @parse $state
+            [
+                {
+                    [pub $(($($flag_vis_args)*))?] $kept $attrs
+                    $flag_kind $flag_name = $flag_value $(=> $flag_char)?
+                }
+                $($entries)*
+            ]
+            $($rest)*
+        }
+    };
+    (
+        @parse $state:tt
+        [{ $flag_vis:tt $kept:tt $attrs:tt $($flag:tt)* } $($entries:tt)*]
+        $($rest:tt)*
+    ) => {
+        compile_error!(concat!(
+            "expected a visibility such as `pub(crate)` in `",
+            stringify!($($flag)*),
+            "`"
+        ));
+    };
+    (@parse $state:tt [] $($rest:tt)*) => {
+        $crate::__neobit_impl! {
+            $($rest)*
+            $state
+        }
+    };
+
+    // `bitflags!` only: drops the derives of traits that are implemented
+    // anyway, then continues with the tokens in braces.
+    (
+        @derives [$($kept:tt)*] [#[derive($($derives:tt)*)] $($attrs:tt)*]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @derives [$($kept)*] @list [] [$($derives)* ,] [$($attrs)*]
+            $($rest)*
+        }
+    };
+    (@derives [$($kept:tt)*] [#[$($attr:tt)*] $($attrs:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_impl! {
+            @derives [$($kept)* #[$($attr)*]] [$($attrs)*]
+            $($rest)*
+        }
+    };
+    (@derives [$($kept:tt)*] [] {$($next:tt)*} $($rest:tt)*) => {
+        $crate::__neobit_impl! {
+            $($next)*
+            $($kept)*
+            $($rest)*
+        }
+    };
+    (@derives $kept:tt @list $keep:tt [, $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_impl! { @derives $kept @list $keep [$($derives)*] $($rest)* }
+    };
+    (@derives $kept:tt @list $keep:tt [Debug, $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_impl! { @derives $kept @list $keep [$($derives)*] $($rest)* }
+    };
+    (@derives $kept:tt @list $keep:tt [Clone, $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_impl! { @derives $kept @list $keep [$($derives)*] $($rest)* }
+    };
+    (@derives $kept:tt @list $keep:tt [Copy, $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_impl! { @derives $kept @list $keep [$($derives)*] $($rest)* }
+    };
+    (@derives $kept:tt @list $keep:tt [PartialEq, $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_impl! { @derives $kept @list $keep [$($derives)*] $($rest)* }
+    };
+    (@derives $kept:tt @list $keep:tt [Eq, $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_impl! { @derives $kept @list $keep [$($derives)*] $($rest)* }
+    };
+    (@derives $kept:tt @list $keep:tt [PartialOrd, $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_impl! { @derives $kept @list $keep [$($derives)*] $($rest)* }
+    };
+    (@derives $kept:tt @list $keep:tt [Ord, $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_impl! { @derives $kept @list $keep [$($derives)*] $($rest)* }
+    };
+    (@derives $kept:tt @list $keep:tt [Hash, $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_impl! { @derives $kept @list $keep [$($derives)*] $($rest)* }
+    };
+    (@derives $kept:tt @list $keep:tt [Default, $($derives:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_impl! { @derives $kept @list $keep [$($derives)*] $($rest)* }
+    };
+    (
+        @derives $kept:tt @list [$($keep:path),*] [$derive:path, $($derives:tt)*]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @derives $kept @list [$($keep,)* $derive] [$($derives)*]
+            $($rest)*
+        }
+    };
+    (@derives [$($kept:tt)*] @list [] [] $attrs:tt $($rest:tt)*) => {
+        $crate::__neobit_impl! { @derives [$($kept)*] $attrs $($rest)* }
+    };
+    (@derives [$($kept:tt)*] @list [$($keep:path),+] [] $attrs:tt $($rest:tt)*) => {
+        $crate::__neobit_impl! {
+            @derives [$($kept)* #[derive($($keep),+)]] $attrs
+            $($rest)*
+        }
+    };
+
+    (
+        @attrs $options:tt [$($attrs:tt)*]
+        #[neobit($($option:tt)*)]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @options $options [$($option)*] [$($attrs)*]
+            $($rest)*
+        }
+    };
+    // `#[repr(transparent)]` is emitted once at the end, since the `consts`
+    // option also requires it.
+    (
+        @attrs {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: $impls:tt,
+            transparent: $_transparent:ident
+        }
+        [$($attrs:tt)*]
+        #[repr(transparent)]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @attrs {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: $zero,
+                impls: $impls,
+                transparent: true
+            }
+            [$($attrs)*]
+            $($rest)*
+        }
+    };
+    (
+        @attrs $options:tt [$($attrs:tt)*]
+        #[$($attr:tt)*]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @attrs $options [$($attrs)* #[$($attr)*]]
+            $($rest)*
+        }
+    };
+    (
+        @attrs {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: $impls:tt,
+            transparent: true
+        }
+        [$($attrs:tt)*]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @emit {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: $zero,
+                impls: $impls
+            }
+            $($attrs)*
+            #[repr(transparent)]
+            $($rest)*
+        }
+    };
+    (
+        @attrs {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: $impls:tt,
+            transparent: false
+        }
+        [$($attrs:tt)*]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @emit {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: $zero,
+                impls: $impls
+            }
+            $($attrs)*
+            $($rest)*
+        }
+    };
+
+    (
+        @options {
+            complement: $_complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: $impls:tt,
+            transparent: $transparent:ident
+        }
+        [complement = $complement:ident $(, $($option:tt)*)?]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @options {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: $zero,
+                impls: $impls,
+                transparent: $transparent
+            }
+            [$($($option)*)?]
+            $($rest)*
+        }
+    };
+    (
+        @options {
+            complement: $complement:ident,
+            flag_enum: $_flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: $impls:tt,
+            transparent: $transparent:ident
+        }
+        [flag_enum = $flag_enum:ident { $($variant:ident),* $(,)? } $(, $($option:tt)*)?]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @options {
+                complement: $complement,
+                flag_enum: [$flag_enum [$($variant)*]],
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: $zero,
+                impls: $impls,
+                transparent: $transparent
+            }
+            [$($($option)*)?]
+            $($rest)*
+        }
+    };
+    (
+        @options $options:tt
+        [flag_enum = $flag_enum:ident $(, $($option:tt)*)?]
+        $($rest:tt)*
+    ) => {
+        compile_error!(concat!(
+            "`flag_enum = ",
+            stringify!($flag_enum),
+            "` needs the variant names in flag order, e.g. `flag_enum = ",
+            stringify!($flag_enum),
+            " { Read, Write }`"
+        ));
+    };
+    (
+        @options {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $_consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: $impls:tt,
+            transparent: $_transparent:ident
+        }
+        [consts = $consts:ident $(, $($option:tt)*)?]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @options {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: [$consts],
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: $zero,
+                impls: $impls,
+                transparent: true
+            }
+            [$($($option)*)?]
+            $($rest)*
+        }
+    };
+    (
+        @options {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $_unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: $impls:tt,
+            transparent: $transparent:ident
+        }
+        [unset_char = $unset_char:literal $(, $($option:tt)*)?]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @options {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: $zero,
+                impls: $impls,
+                transparent: $transparent
+            }
+            [$($($option)*)?]
+            $($rest)*
+        }
+    };
+    (
+        @options {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: { from: $from:ident, unknown: $unknown:ident, ord: $ord:ident, debug: $debug:ident, metadata: $metadata:ident },
+            transparent: $transparent:ident
+        }
+        [no_from $(, $($option:tt)*)?]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @options {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: $zero,
+                impls: { from: false, unknown: $unknown, ord: $ord, debug: $debug, metadata: $metadata },
+                transparent: $transparent
+            }
+            [$($($option)*)?]
+            $($rest)*
+        }
+    };
+    (
+        @options {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: { from: $from:ident, unknown: $unknown:ident, ord: $ord:ident, debug: $debug:ident, metadata: $metadata:ident },
+            transparent: $transparent:ident
+        }
+        [no_ord $(, $($option:tt)*)?]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @options {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: $zero,
+                impls: { from: $from, unknown: $unknown, ord: false, debug: $debug, metadata: $metadata },
+                transparent: $transparent
+            }
+            [$($($option)*)?]
+            $($rest)*
+        }
+    };
+    (
+        @options {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: { from: $from:ident, unknown: $unknown:ident, ord: $ord:ident, debug: $debug:ident, metadata: $metadata:ident },
+            transparent: $transparent:ident
+        }
+        [debug = "names" $(, $($option:tt)*)?]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @options {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: $zero,
+                impls: { from: $from, unknown: $unknown, ord: $ord, debug: names, metadata: $metadata },
+                transparent: $transparent
+            }
+            [$($($option)*)?]
+            $($rest)*
+        }
+    };
+    (
+        @options {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: { from: $from:ident, unknown: $unknown:ident, ord: $ord:ident, debug: $debug:ident, metadata: $metadata:ident },
+            transparent: $transparent:ident
+        }
+        [debug = "custom" $(, $($option:tt)*)?]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @options {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: $zero,
+                impls: { from: $from, unknown: $unknown, ord: $ord, debug: custom, metadata: $metadata },
+                transparent: $transparent
+            }
+            [$($($option)*)?]
+            $($rest)*
+        }
+    };
+    // Invalid policies are reported by `__neobit_unknown!`.
+    (
+        @options {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: { from: $from:ident, unknown: $_unknown:ident, ord: $ord:ident, debug: $debug:ident, metadata: $metadata:ident },
+            transparent: $transparent:ident
+        }
+        [unknown = $unknown:ident $(, $($option:tt)*)?]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @options {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: $zero,
+                impls: { from: $from, unknown: $unknown, ord: $ord, debug: $debug, metadata: $metadata },
+                transparent: $transparent
+            }
+            [$($($option)*)?]
+            $($rest)*
+        }
+    };
+    (
+        @options {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $_zero:tt,
+            impls: $impls:tt,
+            transparent: $transparent:ident
+        }
+        [zero = $zero:ident $(, $($option:tt)*)?]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @options {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: [$zero],
+                impls: $impls,
+                transparent: $transparent
+            }
+            [$($($option)*)?]
+            $($rest)*
+        }
+    };
+    (
+        @options {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: { from: $from:ident, unknown: $unknown:ident, ord: $ord:ident, debug: $debug:ident, metadata: $metadata:ident },
+            transparent: $transparent:ident
+        }
+        [no_metadata $(, $($option:tt)*)?]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @options {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: $numbering,
+                zero: $zero,
+                impls: { from: $from, unknown: $unknown, ord: $ord, debug: $debug, metadata: false },
+                transparent: $transparent
+            }
+            [$($($option)*)?]
+            $($rest)*
+        }
+    };
+    (
+        @options {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $_numbering:ident,
+            zero: $zero:tt,
+            impls: $impls:tt,
+            transparent: $transparent:ident
+        }
+        [msb0 $(, $($option:tt)*)?]
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @options {
+                complement: $complement,
+                flag_enum: $flag_enum,
+                consts: $consts,
+                unset_char: $unset_char,
+                numbering: msb0,
+                zero: $zero,
+                impls: $impls,
+                transparent: $transparent
+            }
+            [$($($option)*)?]
+            $($rest)*
+        }
+    };
+    (
+        @options $options:tt [] $attrs:tt
+        $($rest:tt)*
+    ) => {
+        $crate::__neobit_impl! {
+            @attrs $options $attrs
+            $($rest)*
+        }
+    };
+    (
+        @options $options:tt [$($option:tt)*]
+        $($rest:tt)*
+    ) => {
+        compile_error!(concat!(
+            "unknown or malformed neobit option: `",
+            stringify!($($option)*),
+            "`"
+        ));
+    };
+
+    (
+        @emit {
+            complement: $complement:ident,
+            flag_enum: $flag_enum:tt,
+            consts: $consts:tt,
+            unset_char: $unset_char:tt,
+            numbering: $numbering:ident,
+            zero: $zero:tt,
+            impls: { from: $from:ident, unknown: $unknown:ident, ord: $ord:ident, debug: $debug:ident, metadata: $metadata:ident }
+        }
         $(#[$meta:meta])*
-        $vis:vis struct $name:ident: $int_ty:ty {
-            $(
-                $(#[$const_meta:meta])*
-                const $flag_name:ident = $flag_value:expr;
-            )*
+        $vis:vis struct $name:ident: $int_ty:ty;
+        {
+            flags: [$({
+                [$($flag_vis:tt)*] [$(#[$($flag_meta:tt)*])*]
+                $flag_kind:ident $flag_name:ident = $flag_value:expr
+            })*],
+            names: $flag_names:tt,
+            canonical: [$($canonical:ident)*],
+            parse: [$($parse:ident)*],
+            chars: [$($char_flag:ident $flag_char:literal)*],
+            known: [$($known:tt)*]
         }
     ) => {
         $(#[$meta])*
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+        #[derive(Copy, Clone, Eq, PartialEq, Hash)]
         $vis struct $name {
             bits: $int_ty,
         }
 
         impl $name {
             $(
-                $(#[$const_meta])*
-                pub const $flag_name: Self = Self { bits: $flag_value };
+                $crate::__neobit_flag! {
+                    @attrs [] [$(#[$($flag_meta)*])*]
+                    @const $name [$numbering $int_ty] $flag_names [$($flag_vis)*]
+                    $flag_kind $flag_name = $flag_value;
+                }
             )*
 
-
             /// Creates an empty flags value (all bits unset).
             ///
             /// # Example
@@ -157,62 +2964,266 @@ macro_rules! neobit {
             /// assert_eq!(flags, Flags::A);
             /// ```
             #[inline(always)]
-            pub const fn from_bits_truncate(bits: $int_ty) -> Self {
-                Self::from_bits_retain(bits & Self::all().bits())
+            pub const fn from_bits_truncate(bits: $int_ty) -> Self {
+                Self::from_bits_retain(bits & Self::all().bits())
+            }
+
+
+            /// Creates a flags value from raw bits, retaining all bits.
+            ///
+            /// This does not validate the bits - unknown bits are preserved.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; } }
+            /// let flags = Flags::from_bits_retain(0xFF);
+            /// assert_eq!(flags.bits(), 0xFF);
+            /// ```
+            #[inline(always)]
+            pub const fn from_bits_retain(bits: $int_ty) -> Self {
+                Self { bits }
+            }
+
+            /// Returns the raw bit value.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// let flags = Flags::A | Flags::B;
+            /// assert_eq!(flags.bits(), 0b11);
+            /// ```
+            #[inline(always)]
+            pub const fn bits(self) -> $int_ty {
+                self.bits
+            }
+
+            /// Converts to a flag type of the same width, keeping every bit.
+            ///
+            /// Meant for sibling types declaring the same flags, such as an
+            /// `i32` C API type and a `u32` internal type. Fails to compile if
+            /// the widths differ; use `widen()` or `try_narrow()` instead.
+            ///
+            /// Bits unknown to the target follow its `unknown` option: they
+            /// are kept, or dropped under `unknown = truncate`. A target
+            /// declared with `unknown = reject` fails to compile; use
+            /// `try_narrow()`, which checks the bits.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct CFlags: i32 { const A = 1; const HIGH = i32::MIN; } }
+            /// # neobit! { pub struct Flags: u32 { const A = 1; const HIGH = 1 << 31; } }
+            /// let flags: Flags = (CFlags::A | CFlags::HIGH).cast();
+            /// assert_eq!(flags, Flags::A | Flags::HIGH);
+            /// ```
+            #[inline]
+            pub fn cast<F: $crate::Flags>(self) -> F {
+                let () = $crate::__private::Widths::<Self, F>::SAME;
+                let () = $crate::__private::Widths::<Self, F>::INFALLIBLE;
+                $crate::__private::convert(self)
+            }
+
+            /// Converts to a flag type at least as wide, zero-extending.
+            ///
+            /// Bits are never sign-extended, unlike `bits() as u64` on a
+            /// signed repr. Fails to compile if the target is narrower.
+            /// Unknown bits follow the target's `unknown` option as in
+            /// `cast()`.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Wire: i16 { const A = 1; const HIGH = i16::MIN; } }
+            /// # neobit! { pub struct Flags: u32 { const A = 1; const HIGH = 1 << 15; } }
+            /// let flags: Flags = Wire::HIGH.widen();
+            /// assert_eq!(flags.bits(), 0x8000);
+            /// ```
+            #[inline]
+            pub fn widen<F: $crate::Flags>(self) -> F {
+                let () = $crate::__private::Widths::<Self, F>::NOT_NARROWER;
+                let () = $crate::__private::Widths::<Self, F>::INFALLIBLE;
+                $crate::__private::convert(self)
+            }
+
+            /// Converts to a flag type of any width, failing if set bits do
+            /// not fit in the target, or if they are unknown to a target
+            /// declared with `unknown = reject`. Under `unknown = truncate`
+            /// unknown bits that fit are dropped.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u32 { const A = 1; const B = 1 << 20; } }
+            /// # neobit! { pub struct Wire: u16 { const A = 1; } }
+            /// assert_eq!(Flags::A.try_narrow::<Wire>(), Ok(Wire::A));
+            /// assert!(Flags::B.try_narrow::<Wire>().is_err());
+            /// ```
+            #[inline]
+            pub fn try_narrow<F: $crate::Flags>(
+                self,
+            ) -> ::core::result::Result<F, $crate::NarrowError<Self>> {
+                match $crate::__private::try_convert(self) {
+                    ::core::option::Option::Some(flags) => ::core::result::Result::Ok(flags),
+                    ::core::option::Option::None => {
+                        ::core::result::Result::Err($crate::__private::narrow_error::<Self, F>(self))
+                    }
+                }
+            }
+
+            /// Sets or removes the specified flags based on a boolean condition.
+            ///
+            /// If `condition` is `true`, the flags in `other` are inserted.
+            /// If `condition` is `false`, the flags in `other` are removed.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// let mut flags = Flags::A;
+            /// flags.set(Flags::B, true);
+            /// assert_eq!(flags, Flags::A | Flags::B);
+            /// flags.set(Flags::A, false);
+            /// assert_eq!(flags, Flags::B);
+            /// ```
+            pub fn set(&mut self, other: Self, condition: bool) {
+                let m = (condition as $int_ty).wrapping_neg();
+                self.bits = (self.bits & !other.bits) | (other.bits & m);
+            }
+
+            /// Returns the bits selected by `mask`, shifted down by the mask's
+            /// trailing zeros.
+            ///
+            /// The shift is logical for every repr, so a field touching the
+            /// sign bit of a signed type is not sign-extended.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Reg: u8 { const EN = 1; } }
+            /// const MODE: Reg = Reg::from_bits_retain(0b0011_1000);
+            /// assert_eq!(Reg::from_bits_retain(0b0010_1001).field(MODE), 0b101);
+            /// ```
+            #[inline(always)]
+            pub const fn field(self, mask: Self) -> $int_ty {
+                // The bits below the mask are cleared, so rotating them into
+                // the top is the same as a logical shift.
+                (self.bits & mask.bits).rotate_right(mask.bits.trailing_zeros())
+            }
+
+            /// Returns a copy of `self` with the bits selected by `mask`
+            /// replaced by `value`, shifted up by the mask's trailing zeros.
+            ///
+            /// Bits outside `mask` are left untouched. Returns `None` if `value`
+            /// does not fit in the mask.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Reg: u8 { const EN = 1; } }
+            /// const MODE: Reg = Reg::from_bits_retain(0b0011_1000);
+            /// let reg = Reg::EN.with_field(MODE, 0b101).unwrap();
+            /// assert_eq!(reg.bits(), 0b0010_1001);
+            /// assert_eq!(Reg::EN.with_field(MODE, 0b1000), None);
+            /// ```
+            #[inline(always)]
+            pub const fn with_field(self, mask: Self, value: $int_ty) -> ::core::option::Option<Self> {
+                let shifted = value.rotate_left(mask.bits.trailing_zeros());
+                if shifted & !mask.bits != 0 {
+                    return ::core::option::Option::None;
+                }
+                ::core::option::Option::Some(Self { bits: (self.bits & !mask.bits) | shifted })
+            }
+
+            /// Returns the register value that clears the `#[w1c]` flags in
+            /// `flags` when written.
+            ///
+            /// Only those bits are set; flags in `flags` that are not `#[w1c]`
+            /// are ignored.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Status: u8 { const EN = 1; #[w1c] const RXNE = 2; #[w1c] const OVR = 4; } }
+            /// let value = Status::write_value_to_clear(Status::OVR | Status::EN);
+            /// assert_eq!(value, Status::OVR);
+            /// ```
+            #[inline(always)]
+            pub const fn write_value_to_clear(flags: Self) -> Self {
+                Self { bits: flags.bits & <Self as $crate::Flags>::W1C_MASK.bits }
             }
 
-
-            /// Creates a flags value from raw bits, retaining all bits.
+            /// Read-modify-write helper: applies `f` to the value read from the
+            /// register and returns the value to write back, with every
+            /// `#[w1c]` and `#[ro]` flag masked to zero.
             ///
-            /// This does not validate the bits - unknown bits are preserved.
+            /// Writing back `self` unchanged would clear every pending `#[w1c]`
+            /// flag. Combine the result with `write_value_to_clear()` to clear
+            /// specific flags in the same write.
             ///
             /// # Example
             ///
             /// ```rust
             /// # use neobit::neobit;
-            /// # neobit! { pub struct Flags: u8 { const A = 1; } }
-            /// let flags = Flags::from_bits_retain(0xFF);
-            /// assert_eq!(flags.bits(), 0xFF);
+            /// # neobit! { pub struct Status: u8 { const EN = 1; #[w1c] const RXNE = 2; #[w1c] const OVR = 4; #[ro] const BUSY = 8; } }
+            /// let read = Status::RXNE | Status::OVR | Status::BUSY;
+            /// let write = read.modify_safe(|r| r.insert(Status::EN));
+            /// assert_eq!(write, Status::EN);
+            ///
+            /// let write = write | Status::write_value_to_clear(Status::OVR);
+            /// assert_eq!(write, Status::EN | Status::OVR);
             /// ```
-            #[inline(always)]
-            pub const fn from_bits_retain(bits: $int_ty) -> Self {
-                Self { bits }
+            #[inline]
+            pub fn modify_safe(self, f: impl FnOnce(&mut Self)) -> Self {
+                let mut value = self;
+                f(&mut value);
+                value
+                    .difference(<Self as $crate::Flags>::W1C_MASK)
+                    .difference(<Self as $crate::Flags>::RO_MASK)
             }
 
-            /// Returns the raw bit value.
+            /// Returns `true` if reading the register clears flags, i.e. some
+            /// flags are marked `#[rc]`. Such a register must not be read
+            /// speculatively, e.g. for logging.
             ///
             /// # Example
             ///
             /// ```rust
             /// # use neobit::neobit;
-            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
-            /// let flags = Flags::A | Flags::B;
-            /// assert_eq!(flags.bits(), 0b11);
+            /// # neobit! { pub struct Status: u8 { const EN = 1; #[rc] const OVR = 4; } }
+            /// # neobit! { pub struct Control: u8 { const EN = 1; } }
+            /// assert!(Status::read_has_side_effects());
+            /// assert!(!Control::read_has_side_effects());
             /// ```
             #[inline(always)]
-            pub const fn bits(self) -> $int_ty {
-                self.bits
+            pub const fn read_has_side_effects() -> bool {
+                <Self as $crate::Flags>::RC_MASK.bits != 0
             }
 
-            /// Sets or removes the specified flags based on a boolean condition.
-            ///
-            /// If `condition` is `true`, the flags in `other` are inserted.
-            /// If `condition` is `false`, the flags in `other` are removed.
+            /// Returns the register contents after the read that returned
+            /// `self`: the same value with every `#[rc]` flag cleared.
             ///
             /// # Example
             ///
             /// ```rust
             /// # use neobit::neobit;
-            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
-            /// let mut flags = Flags::A;
-            /// flags.set(Flags::B, true);
-            /// assert_eq!(flags, Flags::A | Flags::B);
-            /// flags.set(Flags::A, false);
-            /// assert_eq!(flags, Flags::B);
+            /// # neobit! { pub struct Status: u8 { const EN = 1; #[rc] const OVR = 4; } }
+            /// let read = Status::EN | Status::OVR;
+            /// assert_eq!(read.after_read(), Status::EN);
             /// ```
-            pub fn set(&mut self, other: Self, condition: bool) {
-                let m = (condition as $int_ty).wrapping_neg();
-                self.bits = (self.bits & !other.bits) | (other.bits & m);
+            #[inline(always)]
+            pub const fn after_read(self) -> Self {
+                Self { bits: self.bits & !<Self as $crate::Flags>::RC_MASK.bits }
             }
 
             /// Returns the union of two flags (OR).
@@ -258,7 +3269,10 @@ macro_rules! neobit {
 
             /// Returns the bitwise complement (NOT).
             ///
-            /// This is the `const fn` equivalent of the `!` operator.
+            /// This is the `const fn` equivalent of the `!` operator, unless the
+            /// type was declared with `#[neobit(complement = known)]`. With
+            /// `#[neobit(complement = masked)]` it is masked by `all()` like in
+            /// bitflags.
             ///
             /// # Semantic Difference from bitflags
             ///
@@ -271,6 +3285,7 @@ macro_rules! neobit {
             /// let flags = Flags::A;  // 0b01
             /// let complement = flags.complement();  // neobit: !0b01 = 0b11111110
             /// // bitflags would return: !0b01 & 0b11 = 0b10 (only defined flags)
+            /// assert_eq!(flags.complement_known(), Flags::B);
             /// ```
             ///
             /// # Warning
@@ -279,7 +3294,28 @@ macro_rules! neobit {
             /// semantics which may produce unexpected results.
             #[inline(always)]
             pub const fn complement(self) -> Self {
-                Self { bits: !self.bits }
+                $crate::__neobit_complement!(@fn $complement, self)
+            }
+
+            /// Returns the complement restricted to defined flags.
+            ///
+            /// This is `complement()` masked by `all()`: unknown bits are never
+            /// set in the result, and for signed types the result is not
+            /// negative unless a defined flag occupies the sign bit. It matches
+            /// the `!` operator of bitflags.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 0b01; const B = 0b10; } }
+            /// const NOT_A: Flags = Flags::A.complement_known();
+            /// assert_eq!(NOT_A, Flags::B);
+            /// assert_eq!(Flags::from_bits_retain(0x81).complement_known(), Flags::B);
+            /// ```
+            #[inline(always)]
+            pub const fn complement_known(self) -> Self {
+                Self { bits: !self.bits & Self::all().bits }
             }
 
             /// Returns the union of all defined flags.
@@ -294,9 +3330,11 @@ macro_rules! neobit {
             /// assert!(all.contains(Flags::B));
             /// ```
             #[inline(always)]
+            #[allow(deprecated)]
             pub const fn all() -> Self {
                 let mut result = Self { bits: 0 };
-                $(result.bits |= $flag_value;)*
+                $(result.bits |= Self::$canonical.bits;)*
+                $(result.bits |= $known;)*
                 result
             }
 
@@ -350,6 +3388,11 @@ macro_rules! neobit {
 
             /// Returns `true` if all flags in `other` are contained in `self`.
             ///
+            /// Every value contains `empty()`, and so every zero-valued flag
+            /// such as a `zero` name: `flags.contains(Flags::NONE)` is always
+            /// `true`. Test for it with `flags == Flags::NONE` or
+            /// `is_empty()` instead.
+            ///
             /// # Example
             ///
             /// ```rust
@@ -365,6 +3408,199 @@ macro_rules! neobit {
                 (self.bits & other.bits) == other.bits
             }
 
+            /// Returns `true` if every flag in `self` is also in `other`.
+            ///
+            /// Unlike `self <= other`, which compares the raw integers, this is
+            /// set inclusion. See also `neobit::SubsetOrd`.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; const C = 4; } }
+            /// assert!(Flags::A.is_subset(Flags::A | Flags::B));
+            /// assert!(Flags::A.is_subset(Flags::A));
+            /// assert!(!Flags::B.is_subset(Flags::A | Flags::C)); // although B < A | C
+            /// ```
+            #[inline(always)]
+            pub const fn is_subset(self, other: Self) -> bool {
+                other.contains(self)
+            }
+
+            /// Returns `true` if every flag in `other` is also in `self`.
+            ///
+            /// Same as `contains()`.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// assert!((Flags::A | Flags::B).is_superset(Flags::A));
+            /// assert!(!Flags::A.is_superset(Flags::B));
+            /// ```
+            #[inline(always)]
+            pub const fn is_superset(self, other: Self) -> bool {
+                self.contains(other)
+            }
+
+            /// Returns `true` if `self` is a subset of `other` and not equal to it.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// assert!(Flags::A.is_strict_subset(Flags::A | Flags::B));
+            /// assert!(!Flags::A.is_strict_subset(Flags::A));
+            /// ```
+            #[inline(always)]
+            pub const fn is_strict_subset(self, other: Self) -> bool {
+                self.is_subset(other) && self.bits != other.bits
+            }
+
+            /// Returns `true` if `self` and `other` have no flags in common.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// assert!(Flags::A.is_disjoint(Flags::B));
+            /// assert!(!Flags::A.is_disjoint(Flags::A | Flags::B));
+            /// assert!(Flags::empty().is_disjoint(Flags::empty()));
+            /// ```
+            #[inline(always)]
+            pub const fn is_disjoint(self, other: Self) -> bool {
+                !self.intersects(other)
+            }
+
+            /// Returns whether `self` and `other` have the same bits, in
+            /// constant time.
+            ///
+            /// Unlike `==`, the comparison has no early exit, and the result
+            /// is a [`Choice`]($crate::Choice) to keep branches out of the
+            /// code that follows.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// assert!(bool::from(Flags::A.ct_eq(Flags::A)));
+            /// assert!(!bool::from(Flags::A.ct_eq(Flags::A | Flags::B)));
+            /// ```
+            #[inline]
+            pub fn ct_eq(self, other: Self) -> $crate::Choice {
+                !$crate::Choice::__from_nonzero((self.bits ^ other.bits) as u128)
+            }
+
+            /// Returns whether all flags in `other` are set, in constant time.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// assert!(bool::from((Flags::A | Flags::B).ct_contains(Flags::A)));
+            /// assert!(!bool::from(Flags::A.ct_contains(Flags::A | Flags::B)));
+            /// ```
+            #[inline]
+            pub fn ct_contains(self, other: Self) -> $crate::Choice {
+                !$crate::Choice::__from_nonzero((other.bits & !self.bits) as u128)
+            }
+
+            /// Returns whether any flag in `other` is set, in constant time.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; const C = 4; } }
+            /// assert!(bool::from((Flags::A | Flags::B).ct_intersects(Flags::B | Flags::C)));
+            /// assert!(!bool::from(Flags::A.ct_intersects(Flags::C)));
+            /// ```
+            #[inline]
+            pub fn ct_intersects(self, other: Self) -> $crate::Choice {
+                $crate::Choice::__from_nonzero((self.bits & other.bits) as u128)
+            }
+
+            /// Returns `a` if `choice` is false and `b` if it is true, without
+            /// branching.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// let choice = Flags::A.ct_eq(Flags::A);
+            /// assert_eq!(Flags::ct_select(Flags::A, Flags::B, choice), Flags::B);
+            /// assert_eq!(Flags::ct_select(Flags::A, Flags::B, !choice), Flags::A);
+            /// ```
+            #[inline]
+            pub fn ct_select(a: Self, b: Self, choice: $crate::Choice) -> Self {
+                let mask = choice.__mask() as $int_ty;
+                Self {
+                    bits: a.bits ^ (mask & (a.bits ^ b.bits)),
+                }
+            }
+
+            /// Checks that all flags in `needed` are set.
+            ///
+            /// On failure the error carries the missing flags, `needed - self`,
+            /// and its `Display` lists them by name.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; const C = 4; } }
+            /// let flags = Flags::A | Flags::B;
+            /// assert!(flags.require(Flags::A | Flags::B).is_ok());
+            /// let err = flags.require(Flags::A | Flags::C).unwrap_err();
+            /// assert_eq!(err.flags(), Flags::C);
+            /// assert_eq!(err.to_string(), "missing flags: C");
+            /// ```
+            #[inline]
+            pub const fn require(
+                self,
+                needed: Self,
+            ) -> ::core::result::Result<(), $crate::Missing<Self>> {
+                let missing = needed.difference(self);
+                if missing.is_empty() {
+                    ::core::result::Result::Ok(())
+                } else {
+                    ::core::result::Result::Err($crate::Missing::all_of(missing))
+                }
+            }
+
+            /// Checks that at least one flag in `candidates` is set.
+            ///
+            /// On failure the error carries `candidates`, none of which are set.
+            /// Like `intersects()`, this fails for empty `candidates`.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; const C = 4; } }
+            /// let flags = Flags::A;
+            /// assert!(flags.require_any(Flags::A | Flags::B).is_ok());
+            /// let err = flags.require_any(Flags::B | Flags::C).unwrap_err();
+            /// assert_eq!(err.to_string(), "missing any of: B | C");
+            /// ```
+            #[inline]
+            pub const fn require_any(
+                self,
+                candidates: Self,
+            ) -> ::core::result::Result<(), $crate::Missing<Self>> {
+                if self.intersects(candidates) {
+                    ::core::result::Result::Ok(())
+                } else {
+                    ::core::result::Result::Err($crate::Missing::any_of(candidates))
+                }
+            }
+
             /// Returns `true` if any flags in `other` are contained in `self`.
             ///
             /// # Example
@@ -410,26 +3646,309 @@ macro_rules! neobit {
             /// assert_eq!(flags, Flags::B);
             /// ```
             #[inline(always)]
-            pub fn remove(&mut self, other: Self) {
-                self.bits &= !other.bits;
+            pub fn remove(&mut self, other: Self) {
+                self.bits &= !other.bits;
+            }
+
+            /// Toggles the flags in `other` in `self`.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// let mut flags = Flags::A;
+            /// flags.toggle(Flags::B);
+            /// assert_eq!(flags, Flags::A | Flags::B);
+            /// flags.toggle(Flags::A);
+            /// assert_eq!(flags, Flags::B);
+            /// ```
+            #[inline(always)]
+            pub fn toggle(&mut self, other: Self) {
+                self.bits ^= other.bits;
+            }
+
+            /// Returns an iterator over the set flags, then any remaining bits
+            /// as a single value.
+            ///
+            /// A flag is yielded if it is contained in `self` and shares a bit
+            /// with the flags not yielded yet, so overlapping flags are not
+            /// repeated. Same order and semantics as bitflags.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// let flags = Flags::A | Flags::B | Flags::from(0x80);
+            /// let items: Vec<_> = flags.iter().collect();
+            /// assert_eq!(items, [Flags::A, Flags::B, Flags::from(0x80)]);
+            /// ```
+            #[inline]
+            pub fn iter(self) -> $crate::Iter<Self> {
+                $crate::Iter::new(self)
+            }
+
+            /// Returns an iterator over the names and values of the set flags.
+            ///
+            /// Bits of no defined flag are skipped.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// let flags = Flags::B | Flags::from(0x80);
+            /// let names: Vec<_> = flags.iter_names().map(|(name, _)| name).collect();
+            /// assert_eq!(names, ["B"]);
+            /// ```
+            #[inline]
+            pub fn iter_names(self) -> $crate::IterNames<Self> {
+                $crate::IterNames::new(self)
+            }
+
+            /// Returns the pattern requiring the flags in `must_set` to be set
+            /// and those in `must_clear` to be clear, in `const` context.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::{neobit, FlagPattern};
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// const A_NOT_B: FlagPattern<Flags> = Flags::pattern(Flags::A, Flags::B);
+            /// assert!(Flags::pattern(Flags::empty(), Flags::empty()).matches(Flags::B));
+            /// assert!(A_NOT_B.matches(Flags::A));
+            /// assert!(!A_NOT_B.matches(Flags::A | Flags::B));
+            /// ```
+            #[inline(always)]
+            pub const fn pattern(must_set: Self, must_clear: Self) -> $crate::FlagPattern<Self> {
+                // `as` sign-extends signed types, the mask undoes it
+                const MASK: u128 = u128::MAX >> (128 - <$int_ty>::BITS);
+                $crate::__private::pattern(
+                    must_set.bits as u128 & MASK,
+                    must_clear.bits as u128 & MASK,
+                )
+            }
+
+            /// Returns `true` if every flag `pattern` requires is set in `self`
+            /// and every flag it forbids is clear, like `pattern.matches(self)`
+            /// but in `const` context.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::{neobit, FlagPattern};
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// const A_NOT_B: FlagPattern<Flags> = Flags::pattern(Flags::A, Flags::B);
+            /// const MATCHES: bool = Flags::A.satisfies(A_NOT_B);
+            /// assert!(MATCHES);
+            /// assert!(!(Flags::A | Flags::B).satisfies(A_NOT_B));
+            /// ```
+            #[inline(always)]
+            pub const fn satisfies(self, pattern: $crate::FlagPattern<Self>) -> bool {
+                const MASK: u128 = u128::MAX >> (128 - <$int_ty>::BITS);
+                let (set, clear) = $crate::__private::pattern_bits(&pattern);
+                let value = self.bits as u128 & MASK;
+                value & set == set && value & clear == 0
+            }
+
+            /// Returns the flag with the given name.
+            ///
+            /// Both canonical flag names and aliases are accepted. The match is
+            /// exact and case-sensitive.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; alias FIRST = A; } }
+            /// assert_eq!(Flags::from_name("A"), Some(Flags::A));
+            /// assert_eq!(Flags::from_name("FIRST"), Some(Flags::A));
+            /// assert_eq!(Flags::from_name("a"), None);
+            /// ```
+            #[allow(deprecated)]
+            pub fn from_name(name: &str) -> ::core::option::Option<Self> {
+                match name {
+                    $(stringify!($parse) => ::core::option::Option::Some(Self::$parse),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            /// Renders `self` into `buf` in the `Debug` format, e.g. `Flags(A | B | 0x80)`.
+            ///
+            /// This neither allocates nor uses `core::fmt`, for targets where the
+            /// formatting machinery is too large. Returns the written text, or
+            /// `BufferTooSmall` if it does not fit.
+            ///
+            /// # Example
+            ///
+            /// ```rust
+            /// # use neobit::neobit;
+            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
+            /// let mut buf = [0u8; 32];
+            /// let text = (Flags::A | Flags::from(0x80)).write_names(&mut buf).unwrap();
+            /// assert_eq!(text, "Flags(A | 0x80)");
+            /// assert!(Flags::A.write_names(&mut [0u8; 4]).is_err());
+            /// ```
+            pub fn write_names(
+                self,
+                buf: &mut [u8],
+            ) -> ::core::result::Result<&str, $crate::BufferTooSmall> {
+                let mut writer = $crate::__private::BufWriter::new(buf);
+                self.__neobit_write_names(|s| writer.write_str(s))?;
+                ::core::result::Result::Ok(writer.finish())
+            }
+
+            /// Writes the `Debug` text piece by piece; shared by all renderers.
+            fn __neobit_write_names<E>(
+                self,
+                mut write: impl FnMut(&str) -> ::core::result::Result<(), E>,
+            ) -> ::core::result::Result<(), E> {
+                write(<Self as $crate::Flags>::TYPE_NAME)?;
+                write("(")?;
+                $crate::Flags::__neobit_write_list(self, &mut write)?;
+                write(")")
+            }
+        }
+
+        // SAFETY: the struct is a single integer field
+
+        impl $crate::Flags for $name {
+            type Bits = $int_ty;
+
+            #[allow(deprecated)]
+            const FLAGS: &'static [(&'static str, Self)] = &[
+                $((stringify!($canonical), $name::$canonical),)*
+            ];
+
+            const ALL: Self = $name::all();
+
+            const REPR_NAME: &'static str = stringify!($int_ty);
+
+            const TYPE_NAME: &'static str = stringify!($name);
+
+            #[allow(deprecated)]
+            const W1C_MASK: Self = Self {
+                bits: 0 $(| $crate::__neobit_access_mask!(w1c [$(#[$($flag_meta)*])*] $name::$flag_name.bits))*
+            };
+
+            #[allow(deprecated)]
+            const RO_MASK: Self = Self {
+                bits: 0 $(| $crate::__neobit_access_mask!(ro [$(#[$($flag_meta)*])*] $name::$flag_name.bits))*
+            };
+
+            #[allow(deprecated)]
+            const RC_MASK: Self = Self {
+                bits: 0 $(| $crate::__neobit_access_mask!(rc [$(#[$($flag_meta)*])*] $name::$flag_name.bits))*
+            };
+
+            #[inline(always)]
+            fn bits(self) -> $int_ty {
+                self.bits
+            }
+
+            #[inline(always)]
+            fn from_bits_retain(bits: $int_ty) -> Self {
+                Self { bits }
+            }
+
+            #[inline(always)]
+            fn contains(self, other: Self) -> bool {
+                (self.bits & other.bits) == other.bits
+            }
+
+            const BITS: u32 = <$int_ty>::BITS;
+
+            #[inline(always)]
+            fn __neobit_to_u128(self) -> u128 {
+                // `as` sign-extends signed types, the mask undoes it
+                (self.bits as u128) & (u128::MAX >> (128 - <$int_ty>::BITS))
             }
 
-            /// Toggles the flags in `other` in `self`.
-            ///
-            /// # Example
-            ///
-            /// ```rust
-            /// # use neobit::neobit;
-            /// # neobit! { pub struct Flags: u8 { const A = 1; const B = 2; } }
-            /// let mut flags = Flags::A;
-            /// flags.toggle(Flags::B);
-            /// assert_eq!(flags, Flags::A | Flags::B);
-            /// flags.toggle(Flags::A);
-            /// assert_eq!(flags, Flags::B);
-            /// ```
             #[inline(always)]
-            pub fn toggle(&mut self, other: Self) {
-                self.bits ^= other.bits;
+            fn __neobit_from_u128(bits: u128) -> Self {
+                Self { bits: bits as $int_ty }
+            }
+
+            const __NEOBIT_UNKNOWN: $crate::__private::Unknown = $crate::__neobit_unknown!(@policy $unknown);
+
+            #[inline]
+            fn __neobit_from_foreign(bits: u128) -> ::core::option::Option<Self> {
+                $crate::__neobit_unknown!(@foreign $unknown, Self::__neobit_from_u128(bits))
+            }
+
+            const __NEOBIT_FROM: bool = $from;
+
+            #[inline]
+            fn __neobit_from_bits(bits: $int_ty) -> ::core::option::Option<Self> {
+                $crate::__neobit_unknown!(@convert $from $unknown $int_ty, bits)
+            }
+
+            const __NEOBIT_COMPLEMENT: $crate::__private::Complement = $crate::__neobit_complement!(@policy $complement);
+
+            const __NEOBIT_DEBUG: bool = $crate::__neobit_optional_impl!(@debug $debug);
+
+            #[inline]
+            fn __neobit_count_containing(values: &[Self], flags: Self) -> usize {
+                let flags = flags.bits;
+                values.iter().map(|v| (v.bits & flags == flags) as usize).sum()
+            }
+
+            #[inline]
+            fn __neobit_count_intersecting(values: &[Self], flags: Self) -> usize {
+                let flags = flags.bits;
+                values.iter().map(|v| (v.bits & flags != 0) as usize).sum()
+            }
+
+            #[inline]
+            fn __neobit_insert_all(values: &mut [Self], flags: Self) {
+                for v in values.iter_mut() {
+                    v.bits |= flags.bits;
+                }
+            }
+
+            #[inline]
+            fn __neobit_remove_all(values: &mut [Self], flags: Self) {
+                for v in values.iter_mut() {
+                    v.bits &= !flags.bits;
+                }
+            }
+
+            fn __neobit_write_list<E>(
+                self,
+                write: &mut impl FnMut(&str) -> ::core::result::Result<(), E>,
+            ) -> ::core::result::Result<(), E> {
+                let mut bits = self.bits;
+                let mut first = true;
+
+                for &(name, flag) in <Self as $crate::Flags>::FLAGS {
+                    let value = flag.bits;
+                    let is_single_bit: bool = value != 0 && (value & (value.wrapping_sub(1))) == 0;
+                    if is_single_bit && (bits & value) == value {
+                        if !first {
+                            write(" | ")?;
+                        }
+                        write(name)?;
+                        bits &= !value;
+                        first = false;
+                    }
+                }
+
+                // Output remaining unknown bits as hex
+                if bits != 0 {
+                    if !first {
+                        write(" | ")?;
+                    }
+                    $crate::__private::write_hex(bits as u128, <$int_ty>::BITS, write)?;
+                    first = false;
+                }
+
+                // Empty case, named by the `zero` option if given
+                if first {
+                    write($crate::__neobit_zero!(@name $zero))?;
+                }
+
+                ::core::result::Result::Ok(())
             }
         }
 
@@ -440,13 +3959,36 @@ macro_rules! neobit {
             }
         }
 
-        impl From<$int_ty> for $name {
-            #[inline(always)]
-            fn from(bits: $int_ty) -> Self {
-                Self::from_bits_retain(bits)
+        impl IntoIterator for $name {
+            type Item = Self;
+            type IntoIter = $crate::Iter<Self>;
+
+            #[inline]
+            fn into_iter(self) -> $crate::Iter<Self> {
+                self.iter()
             }
         }
 
+        impl core::iter::FromIterator<$name> for $name {
+            fn from_iter<I: IntoIterator<Item = Self>>(iter: I) -> Self {
+                let mut result = Self::empty();
+                result.extend(iter);
+                result
+            }
+        }
+
+        impl core::iter::Extend<$name> for $name {
+            fn extend<I: IntoIterator<Item = Self>>(&mut self, iter: I) {
+                for flags in iter {
+                    self.insert(flags);
+                }
+            }
+        }
+
+        $crate::__neobit_optional_impl! { from $from $unknown $name $int_ty }
+        $crate::__neobit_optional_impl! { ord $ord $name }
+        $crate::__neobit_optional_impl! { metadata $metadata $name }
+
         impl From<$name> for $int_ty {
             #[inline(always)]
             fn from(flags: $name) -> $int_ty {
@@ -503,7 +4045,7 @@ macro_rules! neobit {
             type Output = Self;
             #[inline(always)]
             fn not(self) -> Self {
-                self.complement()
+                $crate::__neobit_complement!($complement, self)
             }
         }
 
@@ -522,494 +4064,1524 @@ macro_rules! neobit {
             }
         }
 
-        impl core::fmt::Debug for $name {
+        $crate::__neobit_optional_impl! { debug $debug $name }
+
+        $crate::__neobit_impl_defmt!($name);
+        $crate::__neobit_impl_ufmt!($name);
+        $crate::__neobit_impl_subtle!($name);
+
+        impl core::fmt::Binary for $name {
             fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-                write!(f, "{}(", stringify!($name))?;
+                core::fmt::Binary::fmt(&self.bits, f)
+            }
+        }
 
-                #[allow(non_snake_case)]
-                let __NEOBIT_INTERNAL_FLAGS_REGISTRY: &[(&str, $int_ty)] = &[
-                    $((stringify!($flag_name), $flag_value),)*
-                ];
+        impl core::fmt::LowerHex for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                core::fmt::LowerHex::fmt(&self.bits, f)
+            }
+        }
 
-                let mut bits = self.bits;
-                let mut first = true;
+        impl core::fmt::UpperHex for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                core::fmt::UpperHex::fmt(&self.bits, f)
+            }
+        }
 
-                for &(name, value) in __NEOBIT_INTERNAL_FLAGS_REGISTRY {
-                    let is_single_bit: bool = value != 0 && (value & (value.wrapping_sub(1))) == 0;
-                    if is_single_bit && (bits & value) == value {
-                        if !first {
-                            write!(f, " | ")?;
+        impl core::fmt::Octal for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                core::fmt::Octal::fmt(&self.bits, f)
+            }
+        }
+
+        $crate::__neobit_flag_enum! { $flag_enum $vis $name [$($canonical)*] }
+
+        $crate::__neobit_chars! { $name $unset_char [$($char_flag $flag_char)*] }
+
+        $crate::__neobit_consts! {
+            $consts $vis $name $numbering $int_ty
+            [$([$($flag_vis)*] [$(#[$($flag_meta)*])*] $flag_kind $flag_name = $flag_value;)*]
+        }
+
+        $crate::__neobit_zero! { @check $name $zero }
+
+        // Flag constants are only evaluated when used; this evaluates them
+        // all, so that an out-of-range `bit(n)` always fails to compile.
+        #[allow(deprecated)]
+        const _: () = {
+            $(let _ = $name::$flag_name;)*
+        };
+    };
+}
+
+/// Expands the name `Debug` shows for the empty value (`@name`) and the
+/// check that the flag named by the `zero` option is zero (`@check`).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_zero {
+    (@name []) => {
+        "empty"
+    };
+    (@name [$zero:ident]) => {
+        stringify!($zero)
+    };
+    (@check $name:ident []) => {};
+    // Fails with an array length mismatch if the flag has bits set.
+    (@check $name:ident [$zero:ident]) => {
+        #[allow(deprecated)]
+        const _: [(); 0] = [(); ($name::$zero.bits != 0) as usize];
+    };
+}
+
+/// Expands a single flag declaration of [`neobit!`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_flag {
+    // The access-kind markers only feed the `*_MASK` constants and are not
+    // emitted; every other attribute is kept for the constant.
+    (@attrs $kept:tt [#[w1c] $($attrs:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_flag! { @attrs $kept [$($attrs)*] $($rest)* }
+    };
+    (@attrs $kept:tt [#[ro] $($attrs:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_flag! { @attrs $kept [$($attrs)*] $($rest)* }
+    };
+    (@attrs $kept:tt [#[rc] $($attrs:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_flag! { @attrs $kept [$($attrs)*] $($rest)* }
+    };
+    (@attrs [$($kept:tt)*] [#[$($attr:tt)*] $($attrs:tt)*] $($rest:tt)*) => {
+        $crate::__neobit_flag! { @attrs [$($kept)* #[$($attr)*]] [$($attrs)*] $($rest)* }
+    };
+    (@attrs [$($kept:tt)*] [] @const $name:ident $repr:tt $flag_names:tt $flag_vis:tt $($flag:tt)*) => {
+        $crate::__neobit_flag! { @const $name $repr $flag_names $flag_vis $($kept)* $($flag)* }
+    };
+    (@attrs [$($kept:tt)*] [] @ffi $name:ident $int_ty:tt $flag_vis:tt $($flag:tt)*) => {
+        $crate::__neobit_flag! { @ffi $name $int_ty $flag_vis $($kept)* $($flag)* }
+    };
+
+    // Associated constant for a canonical flag.
+    (
+        @const $name:ident [$numbering:ident $int_ty:ty] $flag_names:tt [$($flag_vis:tt)*]
+        $(#[$meta:meta])*
+        const $flag_name:ident = $flag_value:expr;
+    ) => {
+        $(#[$meta])*
+        $($flag_vis)* const $flag_name: Self = Self {
+            bits: {
+                #[allow(dead_code)]
+                const fn bit(position: u32) -> $int_ty {
+                    $crate::__neobit_bit!($numbering $int_ty, position)
+                }
+                $flag_value
+            },
+        };
+    };
+
+    // Associated constant for an alias. The target is evaluated with every
+    // flag name in scope, so `alias FSYNC = SYNC;` resolves to `Self::SYNC`.
+    (
+        @const $name:ident $repr:tt [$($flag_names:ident)*] [$($flag_vis:tt)*]
+        $(#[$meta:meta])*
+        alias $flag_name:ident = $target:expr;
+    ) => {
+        $(#[$meta])*
+        $($flag_vis)* const $flag_name: Self = {
+            $(
+                #[allow(dead_code, deprecated, non_upper_case_globals)]
+                const $flag_names: $name = $name::$flag_names;
+            )*
+            $target
+        };
+    };
+
+    (
+        @const $name:ident $repr:tt $flag_names:tt $flag_vis:tt
+        $(#[$meta:meta])*
+        $flag_kind:ident $flag_name:ident = $flag_value:expr;
+    ) => {
+        compile_error!(concat!(
+            "expected `const` or `alias` before `",
+            stringify!($flag_name),
+            "`, found `",
+            stringify!($flag_kind),
+            "`"
+        ));
+    };
+
+    // Plain integer constant for the `consts` module. Flag values are copied
+    // as written so that binding generators can read them. Flags that are
+    // not `pub` are left out.
+    (
+        @ffi $name:ident $int_ty:tt [pub]
+        $(#[$meta:meta])*
+        const $flag_name:ident = $flag_value:expr;
+    ) => {
+        $(#[$meta])*
+        pub const $flag_name: $int_ty = $flag_value;
+    };
+    (
+        @ffi $name:ident $int_ty:tt [pub]
+        $(#[$meta:meta])*
+        alias $flag_name:ident = $target:expr;
+    ) => {
+        $(#[$meta])*
+        pub const $flag_name: $int_ty = {
+            #[allow(deprecated)]
+            let flag = super::$name::$flag_name;
+            flag.bits
+        };
+    };
+    // Unknown kinds are reported by `@const`.
+    (
+        @ffi $name:ident $int_ty:tt $flag_vis:tt
+        $(#[$meta:meta])*
+        $flag_kind:ident $flag_name:ident = $flag_value:expr;
+    ) => {};
+}
+
+/// Generates the integer constants module requested with
+/// `#[neobit(consts = name)]`, and checks that the flags type has the layout
+/// of its repr.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_consts {
+    ([] $vis:vis $name:ident $numbering:ident $int_ty:ty [$($flags:tt)*]) => {};
+    (
+        [$consts:ident] $vis:vis $name:ident $numbering:ident $int_ty:ty
+        [$($flag_vis:tt [$($flag_attrs:tt)*] $flag_kind:ident $flag_name:ident = $flag_value:expr;)*]
+    ) => {
+        #[doc = concat!(
+            "The flags of [`", stringify!($name), "`](super::", stringify!($name),
+            ") as plain `", stringify!($int_ty), "` constants."
+        )]
+        #[allow(dead_code)]
+        $vis mod $consts {
+            #[allow(unused_imports)]
+            use super::*;
+
+            const fn bit(position: u32) -> $int_ty {
+                $crate::__neobit_bit!($numbering $int_ty, position)
+            }
+
+            $(
+                $crate::__neobit_flag! {
+                    @attrs [] [$($flag_attrs)*]
+                    @ffi $name $int_ty $flag_vis
+                    $flag_kind $flag_name = $flag_value;
+                }
+            )*
+        }
+
+        const _: [(); ::core::mem::size_of::<$int_ty>()] = [(); ::core::mem::size_of::<$name>()];
+        const _: [(); ::core::mem::align_of::<$int_ty>()] = [(); ::core::mem::align_of::<$name>()];
+    };
+}
+
+/// The mask of bit `$position` for `bit(n)` flag values, counted from the
+/// least (`lsb0`) or most (`msb0`) significant bit.
+///
+/// Positions outside the repr are shifted as given, which overflows and
+/// fails constant evaluation.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_bit {
+    (lsb0 $int_ty:ty, $position:expr) => {
+        (1 as $int_ty) << $position
+    };
+    (msb0 $int_ty:ty, $position:expr) => {{
+        let position: u32 = $position;
+        let width = <$int_ty>::BITS;
+        (1 as $int_ty)
+            << if position < width {
+                width - 1 - position
+            } else {
+                position
+            }
+    }};
+}
+
+/// Evaluates to `$flag` if the flag attributes contain the access-kind
+/// marker `#[$kind]`, and to `0` otherwise.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_access_mask {
+    (w1c [#[w1c] $($attrs:tt)*] $flag:expr) => {
+        $flag
+    };
+    (ro [#[ro] $($attrs:tt)*] $flag:expr) => {
+        $flag
+    };
+    (rc [#[rc] $($attrs:tt)*] $flag:expr) => {
+        $flag
+    };
+    ($kind:ident [#[$($attr:tt)*] $($attrs:tt)*] $flag:expr) => {
+        $crate::__neobit_access_mask!($kind [$($attrs)*] $flag)
+    };
+    ($kind:ident [] $flag:expr) => {
+        0
+    };
+}
+
+/// Generates the character-code methods for the flags declared with
+/// `=> 'c'`. Expands to nothing if there are none.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_chars {
+    ($name:ident $unset_char:tt []) => {};
+    ($name:ident $unset_char:tt [$($flag_name:ident $flag_char:literal)+]) => {
+        impl $name {
+            /// The flags declared with a display character, and their
+            /// characters, in declaration order.
+            #[allow(deprecated)]
+            pub const CHARS: &'static [(Self, char)] = &[$((Self::$flag_name, $flag_char),)+];
+
+            /// Renders one character per flag in `CHARS`, e.g. `rw-`: the
+            /// flag's character if it is set, the unset character otherwise.
+            ///
+            /// The result has a fixed capacity and is not allocated.
+            pub fn to_char_string(
+                self,
+            ) -> $crate::CharString<{ $crate::__private::char_string_len($name::CHARS, $unset_char) }> {
+                $crate::__private::char_string(|write| self.__neobit_write_chars(write))
+            }
+
+            /// Renders `to_char_string()` into `buf`. Returns the written
+            /// text, or `BufferTooSmall` if it does not fit.
+            pub fn write_char_string(
+                self,
+                buf: &mut [u8],
+            ) -> ::core::result::Result<&str, $crate::BufferTooSmall> {
+                let mut writer = $crate::__private::BufWriter::new(buf);
+                self.__neobit_write_chars(&mut |s: &str| writer.write_str(s))?;
+                ::core::result::Result::Ok(writer.finish())
+            }
+
+            /// Parses the output of `to_char_string()`.
+            ///
+            /// Each position accepts its flag's character or the unset
+            /// character. Needs no allocation.
+            pub fn from_char_string(
+                s: &str,
+            ) -> ::core::result::Result<Self, $crate::CharStringError> {
+                let expected = Self::CHARS.len();
+                let mut result = Self::empty();
+                let mut chars = s.chars();
+
+                for (position, &(flag, set)) in Self::CHARS.iter().enumerate() {
+                    match chars.next() {
+                        ::core::option::Option::Some(c) if c == set => result.insert(flag),
+                        ::core::option::Option::Some(c) if c == $unset_char => {}
+                        ::core::option::Option::Some(_) => {
+                            return ::core::result::Result::Err(
+                                $crate::CharStringError::InvalidChar { position },
+                            );
+                        }
+                        ::core::option::Option::None => {
+                            return ::core::result::Result::Err(
+                                $crate::CharStringError::WrongLength { expected, found: position },
+                            );
                         }
-                        write!(f, "{}", name)?;
-                        bits &= !value;
-                        first = false;
                     }
                 }
 
-                // Output remaining unknown bits as hex
-                if bits != 0 {
-                    if !first {
-                        write!(f, " | ")?;
-                    }
-                    write!(f, "{:#x}", bits)?;
-                    first = false;
+                let extra = chars.count();
+                if extra != 0 {
+                    return ::core::result::Result::Err($crate::CharStringError::WrongLength {
+                        expected,
+                        found: expected + extra,
+                    });
                 }
+                ::core::result::Result::Ok(result)
+            }
 
-                // Empty case
-                if first {
-                    write!(f, "empty")?;
+            fn __neobit_write_chars<E>(
+                self,
+                write: &mut dyn FnMut(&str) -> ::core::result::Result<(), E>,
+            ) -> ::core::result::Result<(), E> {
+                for &(flag, set) in Self::CHARS {
+                    let c = if !flag.is_empty() && self.contains(flag) { set } else { $unset_char };
+                    write(c.encode_utf8(&mut [0; 4]))?;
+                }
+                ::core::result::Result::Ok(())
+            }
+        }
+    };
+}
+
+/// Generates the companion enum requested with
+/// `#[neobit(flag_enum = Name { Variant, ... })]`. The variants map to the
+/// canonical flags by position.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_flag_enum {
+    ([] $vis:vis $name:ident $canonical:tt) => {};
+    ([$flag_enum:ident [$($variant:ident)*]] $vis:vis $name:ident [$($flag:ident)*]) => {
+        #[doc = concat!("The individual flags of [`", stringify!($name), "`].")]
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+        $vis enum $flag_enum {
+            $(
+                #[doc = concat!("A flag of [`", stringify!($name), "`], see [`", stringify!($flag_enum), "::flag`].")]
+                $variant,
+            )*
+        }
+
+        // Fails with an array length mismatch unless there is one variant per flag.
+        const _: [(); 0] = [(); (<[&str]>::len(&[$(stringify!($variant)),*])
+            != <[&str]>::len(&[$(stringify!($flag)),*])) as usize];
+
+        impl $flag_enum {
+            /// Every variant, in declaration order.
+            pub const ALL_VARIANTS: &'static [Self] = &[$(Self::$variant),*];
+
+            /// Returns the flags value of this variant.
+            #[inline(always)]
+            pub const fn flag(self) -> $name {
+                #[allow(deprecated)]
+                const FLAGS: &[$name] = &[$($name::$flag),*];
+                FLAGS[self as usize]
+            }
+
+            /// Returns the name of the flag, e.g. `"READ"` for `Read`.
+            pub const fn name(self) -> &'static str {
+                const NAMES: &[&str] = &[$(stringify!($flag)),*];
+                NAMES[self as usize]
+            }
+        }
+
+        impl From<$flag_enum> for $name {
+            #[inline(always)]
+            fn from(flag: $flag_enum) -> $name {
+                flag.flag()
+            }
+        }
+
+        impl ::core::convert::TryFrom<$name> for $flag_enum {
+            type Error = $name;
+
+            /// Converts a value that is exactly one flag, returning the value
+            /// back as the error otherwise.
+            fn try_from(flags: $name) -> ::core::result::Result<Self, $name> {
+                for &variant in Self::ALL_VARIANTS {
+                    if variant.flag() == flags && !flags.is_empty() {
+                        return ::core::result::Result::Ok(variant);
+                    }
                 }
+                ::core::result::Result::Err(flags)
+            }
+        }
+
+        impl $name {
+            #[doc = concat!("Returns an iterator over the [`", stringify!($flag_enum), "`] variants set in `self`.")]
+            ///
+            /// Variants are yielded in declaration order. A variant is yielded
+            /// when all of its bits are set and at least one of them was not
+            /// covered by a previously yielded variant. Unknown bits are ignored.
+            pub fn iter_variants(self) -> impl Iterator<Item = $flag_enum> {
+                let mut remaining = self;
+                $flag_enum::ALL_VARIANTS.iter().copied().filter(move |variant| {
+                    let flag = variant.flag();
+                    if self.contains(flag) && remaining.intersects(flag) {
+                        remaining.remove(flag);
+                        true
+                    } else {
+                        false
+                    }
+                })
+            }
+        }
+    };
+}
 
-                write!(f, ")")
+/// Emits the impls that can be turned off with the `no_from`, `no_ord` and
+/// `debug` options, the inherent constants left out by `no_metadata`, and
+/// whether the `Debug` impl is generated (`@debug`).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_optional_impl {
+    (from true retain $name:ident $int_ty:ty) => {
+        impl From<$int_ty> for $name {
+            #[inline(always)]
+            fn from(bits: $int_ty) -> Self {
+                Self::from_bits_retain(bits)
+            }
+        }
+    };
+    (from true truncate $name:ident $int_ty:ty) => {
+        impl From<$int_ty> for $name {
+            #[inline(always)]
+            fn from(bits: $int_ty) -> Self {
+                Self::from_bits_truncate(bits)
+            }
+        }
+    };
+    (from true reject $name:ident $int_ty:ty) => {
+        impl ::core::convert::TryFrom<$int_ty> for $name {
+            type Error = $crate::UnknownBits<$name>;
+
+            #[inline]
+            fn try_from(bits: $int_ty) -> ::core::result::Result<Self, Self::Error> {
+                match Self::from_bits(bits) {
+                    ::core::option::Option::Some(flags) => ::core::result::Result::Ok(flags),
+                    ::core::option::Option::None => ::core::result::Result::Err(
+                        $crate::__private::unknown_bits(Self::from_bits_retain(bits), Self::all()),
+                    ),
+                }
+            }
+        }
+    };
+    // Invalid policies are reported by `__neobit_unknown!`.
+    (from $from:ident $unknown:ident $name:ident $int_ty:ty) => {};
+    // Same order as a derive: by the raw bits.
+    (ord true $name:ident) => {
+        impl PartialOrd for $name {
+            #[inline(always)]
+            fn partial_cmp(&self, other: &Self) -> ::core::option::Option<core::cmp::Ordering> {
+                ::core::option::Option::Some(self.cmp(other))
             }
         }
 
-        impl core::fmt::Binary for $name {
-            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-                core::fmt::Binary::fmt(&self.bits, f)
+        impl Ord for $name {
+            #[inline(always)]
+            fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                self.bits.cmp(&other.bits)
             }
         }
+    };
+    (ord false $name:ident) => {};
+    (metadata true $name:ident) => {
+        impl $name {
+            /// The defined flags and their names, in declaration order.
+            ///
+            /// Aliases are not included.
+            pub const FLAGS: &'static [(&'static str, Self)] = <Self as $crate::Flags>::FLAGS;
 
-        impl core::fmt::LowerHex for $name {
+            /// The number of defined flags (the length of `FLAGS`).
+            pub const FLAG_COUNT: usize = <Self as $crate::Flags>::FLAG_COUNT;
+
+            /// The union of all defined flags, same as `all()`.
+            pub const ALL: Self = <Self as $crate::Flags>::ALL;
+
+            /// The name of the underlying integer type, e.g. `"u8"`.
+            pub const REPR_NAME: &'static str = <Self as $crate::Flags>::REPR_NAME;
+
+            /// The name of this type.
+            pub const TYPE_NAME: &'static str = <Self as $crate::Flags>::TYPE_NAME;
+        }
+    };
+    (metadata false $name:ident) => {};
+    (debug names $name:ident) => {
+        impl core::fmt::Debug for $name {
             fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-                core::fmt::LowerHex::fmt(&self.bits, f)
+                self.__neobit_write_names(|s| f.write_str(s))
             }
         }
+    };
+    (debug custom $name:ident) => {};
+    (@debug names) => {
+        true
+    };
+    (@debug custom) => {
+        false
+    };
+}
 
-        impl core::fmt::UpperHex for $name {
-            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-                core::fmt::UpperHex::fmt(&self.bits, f)
-            }
+/// Expands the `unknown` policy: its `__private::Unknown` value (`@policy`),
+/// the conversion of bits from another flag type (`@foreign`) and the
+/// conversion through the generated `From` or `TryFrom` impl (`@convert`).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_unknown {
+    (@policy retain) => {
+        $crate::__private::Unknown::Retain
+    };
+    (@policy truncate) => {
+        $crate::__private::Unknown::Truncate
+    };
+    (@policy reject) => {
+        $crate::__private::Unknown::Reject
+    };
+    (@policy $unknown:ident) => {
+        compile_error!(concat!(
+            "invalid neobit option `unknown = ",
+            stringify!($unknown),
+            "`, expected `retain`, `truncate` or `reject`"
+        ))
+    };
+    (@foreign retain, $flags:expr) => {
+        ::core::option::Option::Some($flags)
+    };
+    (@foreign truncate, $flags:expr) => {
+        ::core::option::Option::Some($flags.intersection(Self::all()))
+    };
+    (@foreign reject, $flags:expr) => {
+        Self::from_bits($flags.bits)
+    };
+    // Invalid policies are reported by `@policy`.
+    (@foreign $unknown:ident, $flags:expr) => {
+        ::core::option::Option::None
+    };
+    (@convert true reject $int_ty:ty, $bits:expr) => {
+        <Self as ::core::convert::TryFrom<$int_ty>>::try_from($bits).ok()
+    };
+    (@convert true $unknown:ident $int_ty:ty, $bits:expr) => {
+        ::core::option::Option::Some(<Self as ::core::convert::From<$int_ty>>::from($bits))
+    };
+    (@convert $from:ident $unknown:ident $int_ty:ty, $bits:expr) => {{
+        let _ = $bits;
+        ::core::option::Option::None
+    }};
+}
+
+/// Expands the bodies of `complement()` (`@fn`) and of the `Not` impl
+/// according to the `complement` option, and its `__private::Complement`
+/// value (`@policy`).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_complement {
+    (@fn masked, $value:expr) => {
+        Self {
+            bits: !$value.bits & Self::all().bits,
         }
+    };
+    // Invalid values are reported by the `Not` impl.
+    (@fn $complement:ident, $value:expr) => {
+        Self { bits: !$value.bits }
+    };
+    (@policy raw) => {
+        $crate::__private::Complement::Raw
+    };
+    (@policy known) => {
+        $crate::__private::Complement::Known
+    };
+    (@policy masked) => {
+        $crate::__private::Complement::Masked
+    };
+    // Invalid values are reported by the `Not` impl.
+    (@policy $complement:ident) => {
+        $crate::__private::Complement::Raw
+    };
+    (raw, $value:expr) => {
+        $value.complement()
+    };
+    (known, $value:expr) => {
+        $value.complement_known()
+    };
+    (masked, $value:expr) => {
+        $value.complement()
+    };
+    ($complement:ident, $value:expr) => {
+        compile_error!(concat!(
+            "invalid neobit option `complement = ",
+            stringify!($complement),
+            "`, expected `raw`, `known` or `masked`"
+        ))
+    };
+}
 
-        impl core::fmt::Octal for $name {
-            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-                core::fmt::Octal::fmt(&self.bits, f)
+/// Implements `defmt::Format` when the `defmt` feature is enabled.
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_impl_defmt {
+    ($name:ident) => {
+        impl $crate::__private::defmt::Format for $name {
+            fn format(&self, f: $crate::__private::defmt::Formatter<'_>) {
+                let _ = self.__neobit_write_names(|s| {
+                    $crate::__private::defmt_str(f, s);
+                    ::core::result::Result::Ok::<(), ()>(())
+                });
             }
         }
     };
 }
 
-#[cfg(kani)]
-mod kani_proofs {
-    //! Kani formal verification proofs for neobit.
-    //!
-    //! These proof harnesses verify that all bitwise operations are panic-free
-    //! and mathematically correct across all possible input combinations.
+/// Implements `defmt::Format` when the `defmt` feature is enabled.
+#[cfg(not(feature = "defmt"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_impl_defmt {
+    ($name:ident) => {};
+}
 
-    // Test flags for verification
-    neobit! {
-        /// Flags used for Kani verification proofs
-        pub struct TestFlags: u8 {
-            const A = 0b0001;
-            const B = 0b0010;
-            const C = 0b0100;
-            const D = 0b1000;
+/// Implements `ufmt::uDebug` when the `ufmt` feature is enabled.
+#[cfg(feature = "ufmt")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_impl_ufmt {
+    ($name:ident) => {
+        impl $crate::__private::ufmt::uDebug for $name {
+            fn fmt<W>(
+                &self,
+                f: &mut $crate::__private::ufmt::Formatter<'_, W>,
+            ) -> ::core::result::Result<(), W::Error>
+            where
+                W: $crate::__private::ufmt::uWrite + ?Sized,
+            {
+                self.__neobit_write_names(|s| f.write_str(s))
+            }
         }
-    }
+    };
+}
+
+/// Implements `ufmt::uDebug` when the `ufmt` feature is enabled.
+#[cfg(not(feature = "ufmt"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_impl_ufmt {
+    ($name:ident) => {};
+}
 
-    /// Verify that union operation never panics for any bit combination.
-    #[kani::proof]
-    fn proof_union_no_panic() {
-        let a: u8 = kani::any();
-        let b: u8 = kani::any();
+/// Implements the `subtle` traits when the `subtle` feature is enabled.
+#[cfg(feature = "subtle")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_impl_subtle {
+    ($name:ident) => {
+        impl $crate::__private::subtle::ConstantTimeEq for $name {
+            fn ct_eq(&self, other: &Self) -> $crate::__private::subtle::Choice {
+                $crate::__private::subtle::Choice::from($name::ct_eq(*self, *other).unwrap_u8())
+            }
+        }
 
-        let flags_a = TestFlags::from_bits_retain(a);
-        let flags_b = TestFlags::from_bits_retain(b);
+        impl $crate::__private::subtle::ConditionallySelectable for $name {
+            fn conditional_select(
+                a: &Self,
+                b: &Self,
+                choice: $crate::__private::subtle::Choice,
+            ) -> Self {
+                $name::ct_select(*a, *b, $crate::Choice::from(choice.unwrap_u8()))
+            }
+        }
+    };
+}
 
-        // Union should never panic
-        let result = flags_a.union(flags_b);
+/// Implements the `subtle` traits when the `subtle` feature is enabled.
+#[cfg(not(feature = "subtle"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __neobit_impl_subtle {
+    ($name:ident) => {};
+}
 
-        // Verify bitwise OR semantics
-        assert_eq!(result.bits(), a | b);
-    }
+/// Generates Kani proof harnesses for a type defined with [`neobit!`].
+///
+/// See the non-Kani definition for documentation.
+#[cfg(kani)]
+#[macro_export]
+macro_rules! neobit_kani_proofs {
+    (mod $module:ident: $ty:ty) => {
+        #[allow(non_snake_case, deprecated)]
+        mod $module {
+            #[allow(unused_imports)]
+            use super::*;
+
+            /// Verify that union operation never panics for any bit combination.
+            #[kani::proof]
+            fn proof_union_no_panic() {
+                let a = kani::any();
+                let b = kani::any();
+
+                let flags_a = <$ty>::from_bits_retain(a);
+                let flags_b = <$ty>::from_bits_retain(b);
+
+                // Union should never panic
+                let result = flags_a.union(flags_b);
+
+                // Verify bitwise OR semantics
+                assert_eq!(result.bits(), a | b);
+            }
 
-    /// Verify that intersection operation never panics for any bit combination.
-    #[kani::proof]
-    fn proof_intersection_no_panic() {
-        let a: u8 = kani::any();
-        let b: u8 = kani::any();
+            /// Verify that intersection operation never panics for any bit combination.
+            #[kani::proof]
+            fn proof_intersection_no_panic() {
+                let a = kani::any();
+                let b = kani::any();
 
-        let flags_a = TestFlags::from_bits_retain(a);
-        let flags_b = TestFlags::from_bits_retain(b);
+                let flags_a = <$ty>::from_bits_retain(a);
+                let flags_b = <$ty>::from_bits_retain(b);
 
-        // Intersection should never panic
-        let result = flags_a.intersection(flags_b);
+                // Intersection should never panic
+                let result = flags_a.intersection(flags_b);
 
-        // Verify bitwise AND semantics
-        assert_eq!(result.bits(), a & b);
-    }
+                // Verify bitwise AND semantics
+                assert_eq!(result.bits(), a & b);
+            }
 
-    /// Verify that difference operation never panics for any bit combination.
-    #[kani::proof]
-    fn proof_difference_no_panic() {
-        let a: u8 = kani::any();
-        let b: u8 = kani::any();
+            /// Verify that difference operation never panics for any bit combination.
+            #[kani::proof]
+            fn proof_difference_no_panic() {
+                let a = kani::any();
+                let b = kani::any();
 
-        let flags_a = TestFlags::from_bits_retain(a);
-        let flags_b = TestFlags::from_bits_retain(b);
+                let flags_a = <$ty>::from_bits_retain(a);
+                let flags_b = <$ty>::from_bits_retain(b);
 
-        // Difference should never panic
-        let result = flags_a.difference(flags_b);
+                // Difference should never panic
+                let result = flags_a.difference(flags_b);
 
-        // Verify bitwise AND NOT semantics
-        assert_eq!(result.bits(), a & !b);
-    }
+                // Verify bitwise AND NOT semantics
+                assert_eq!(result.bits(), a & !b);
+            }
 
-    /// Verify that complement operation never panics for any bit combination.
-    #[kani::proof]
-    fn proof_complement_no_panic() {
-        let a: u8 = kani::any();
+            /// Verify that complement operation never panics for any bit combination.
+            #[kani::proof]
+            fn proof_complement_no_panic() {
+                let a = kani::any();
 
-        let flags = TestFlags::from_bits_retain(a);
+                let flags = <$ty>::from_bits_retain(a);
 
-        // Complement should never panic
-        let result = flags.complement();
+                // Complement should never panic
+                let result = flags.complement();
 
-        // Verify bitwise NOT semantics
-        assert_eq!(result.bits(), !a);
-    }
+                // Verify bitwise NOT semantics, masked by all() for
+                // `complement = masked` types
+                let expected = if <$ty as $crate::Flags>::__NEOBIT_COMPLEMENT
+                    == $crate::__private::Complement::Masked
+                {
+                    !a & <$ty>::all().bits()
+                } else {
+                    !a
+                };
+                assert_eq!(result.bits(), expected);
+            }
 
-    /// Verify that symmetric_difference operation never panics for any bit combination.
-    #[kani::proof]
-    fn proof_symmetric_difference_no_panic() {
-        let a: u8 = kani::any();
-        let b: u8 = kani::any();
+            /// Verify that symmetric_difference operation never panics for any bit combination.
+            #[kani::proof]
+            fn proof_symmetric_difference_no_panic() {
+                let a = kani::any();
+                let b = kani::any();
 
-        let flags_a = TestFlags::from_bits_retain(a);
-        let flags_b = TestFlags::from_bits_retain(b);
+                let flags_a = <$ty>::from_bits_retain(a);
+                let flags_b = <$ty>::from_bits_retain(b);
 
-        // Symmetric difference should never panic
-        let result = flags_a.symmetric_difference(flags_b);
+                // Symmetric difference should never panic
+                let result = flags_a.symmetric_difference(flags_b);
 
-        // Verify bitwise XOR semantics
-        assert_eq!(result.bits(), a ^ b);
-    }
+                // Verify bitwise XOR semantics
+                assert_eq!(result.bits(), a ^ b);
+            }
 
-    /// Verify that from_bits correctly validates bit combinations.
-    ///
-    /// Property: from_bits returns Some if and only if all bits are within defined flags.
-    #[kani::proof]
-    fn proof_from_bits_soundness() {
-        let bits: u8 = kani::any();
-        let all_flags = TestFlags::all().bits();
-
-        let result = TestFlags::from_bits(bits);
-
-        // If bits are valid (no unknown bits set), result should be Some
-        if (bits & !all_flags) == 0 {
-            assert!(result.is_some());
-            assert_eq!(result.unwrap().bits(), bits);
-        } else {
-            // If any unknown bit is set, result should be None
-            assert!(result.is_none());
-        }
+            /// Verify that from_bits correctly validates bit combinations.
+            ///
+            /// Property: from_bits returns Some if and only if all bits are within defined flags.
+            #[kani::proof]
+            fn proof_from_bits_soundness() {
+                let bits = kani::any();
+                let all_flags = <$ty>::all().bits();
+
+                let result = <$ty>::from_bits(bits);
+
+                // If bits are valid (no unknown bits set), result should be Some
+                if (bits & !all_flags) == 0 {
+                    assert!(result.is_some());
+                    assert_eq!(result.unwrap().bits(), bits);
+                } else {
+                    // If any unknown bit is set, result should be None
+                    assert!(result.is_none());
+                }
 
-        // Cover both branches
-        kani::cover!(result.is_some(), "from_bits returns Some for valid bits");
-        kani::cover!(result.is_none(), "from_bits returns None for invalid bits");
-    }
+                // Cover both branches
+                kani::cover!(result.is_some(), "from_bits returns Some for valid bits");
+                kani::cover!(result.is_none(), "from_bits returns None for invalid bits");
+            }
 
-    /// Verify that from_bits_truncate always produces valid flags.
-    ///
-    /// Property: The result only contains bits that are in all().
-    #[kani::proof]
-    fn proof_from_bits_truncate_soundness() {
-        let bits: u8 = kani::any();
-        let all_flags = TestFlags::all().bits();
+            /// Verify that from_bits_truncate always produces valid flags.
+            ///
+            /// Property: The result only contains bits that are in all().
+            #[kani::proof]
+            fn proof_from_bits_truncate_soundness() {
+                let bits = kani::any();
+                let all_flags = <$ty>::all().bits();
 
-        let result = TestFlags::from_bits_truncate(bits);
+                let result = <$ty>::from_bits_truncate(bits);
 
-        // Result should only have valid bits (masked with all flags)
-        assert_eq!(result.bits(), bits & all_flags);
+                // Result should only have valid bits (masked with all flags)
+                assert_eq!(result.bits(), bits & all_flags);
 
-        // Result should always be convertible back via from_bits
-        assert!(TestFlags::from_bits(result.bits()).is_some());
-    }
+                // Result should always be convertible back via from_bits
+                assert!(<$ty>::from_bits(result.bits()).is_some());
+            }
 
-    /// Verify that contains correctly checks flag membership.
-    ///
-    /// Property: contains(other) is true iff (self & other) == other.
-    #[kani::proof]
-    fn proof_contains_correctness() {
-        let a: u8 = kani::any();
-        let b: u8 = kani::any();
+            /// Verify that contains correctly checks flag membership.
+            ///
+            /// Property: contains(other) is true iff (self & other) == other.
+            #[kani::proof]
+            fn proof_contains_correctness() {
+                let a = kani::any();
+                let b = kani::any();
 
-        let flags_a = TestFlags::from_bits_retain(a);
-        let flags_b = TestFlags::from_bits_retain(b);
+                let flags_a = <$ty>::from_bits_retain(a);
+                let flags_b = <$ty>::from_bits_retain(b);
 
-        let contains_result = flags_a.contains(flags_b);
+                let contains_result = flags_a.contains(flags_b);
 
-        // Verify contains semantics
-        assert_eq!(contains_result, (a & b) == b);
+                // Verify contains semantics
+                assert_eq!(contains_result, (a & b) == b);
 
-        // Cover both true and false cases
-        kani::cover!(contains_result, "contains returns true");
-        kani::cover!(!contains_result, "contains returns false");
-    }
+                // Cover both true and false cases
+                kani::cover!(contains_result, "contains returns true");
+                kani::cover!(!contains_result, "contains returns false");
+            }
 
-    /// Verify that set operation never panics and produces correct results.
-    #[kani::proof]
-    fn proof_set_no_panic() {
-        let initial: u8 = kani::any();
-        let other: u8 = kani::any();
-        let condition: bool = kani::any();
+            /// Verify that set operation never panics and produces correct results.
+            #[kani::proof]
+            fn proof_set_no_panic() {
+                let initial = kani::any();
+                let other = kani::any();
+                let condition: bool = kani::any();
 
-        let mut flags = TestFlags::from_bits_retain(initial);
-        let other_flags = TestFlags::from_bits_retain(other);
+                let mut flags = <$ty>::from_bits_retain(initial);
+                let other_flags = <$ty>::from_bits_retain(other);
 
-        // Set should never panic
-        flags.set(other_flags, condition);
+                // Set should never panic
+                flags.set(other_flags, condition);
 
-        // Verify set semantics
-        if condition {
-            // If condition is true, other bits should be added
-            assert!((flags.bits() & other) == other);
-        } else {
-            // If condition is false, other bits should be removed
-            assert!((flags.bits() & other) == 0);
-        }
-    }
+                // Verify set semantics
+                if condition {
+                    // If condition is true, other bits should be added
+                    assert!((flags.bits() & other) == other);
+                } else {
+                    // If condition is false, other bits should be removed
+                    assert!((flags.bits() & other) == 0);
+                }
+            }
 
-    /// Verify that From<u8> and From<TestFlags> are inverses.
-    ///
-    /// Property: Converting from u8 to Flags and back always preserves the value.
-    #[kani::proof]
-    fn proof_roundtrip_conversion() {
-        let bits: u8 = kani::any();
+            /// Verify that `From<int>` and `From<Self>` are inverses.
+            ///
+            /// Property: Converting from the integer type to Flags and back preserves the value.
+            /// With `unknown = truncate` unknown bits are dropped, with `unknown = reject` the
+            /// conversion is `TryFrom<int>` and fails on them, and `no_from` types have none.
+            #[kani::proof]
+            fn proof_roundtrip_conversion() {
+                let bits = <$ty>::from_bits_retain(kani::any()).bits();
+                let known = bits & <$ty>::all().bits();
+                let unknown = <$ty as $crate::Flags>::__NEOBIT_UNKNOWN;
+
+                // int -> Flags -> int should preserve value
+                match <$ty as $crate::Flags>::__neobit_from_bits(bits) {
+                    Some(flags) => {
+                        let result: <$ty as $crate::Flags>::Bits = flags.into();
+                        if unknown == $crate::__private::Unknown::Retain {
+                            assert_eq!(result, bits);
+                        } else {
+                            assert_eq!(result, known);
+                        }
+                    }
+                    None => {
+                        assert!(
+                            !<$ty as $crate::Flags>::__NEOBIT_FROM
+                                || (unknown == $crate::__private::Unknown::Reject && known != bits)
+                        );
+                    }
+                }
+            }
 
-        // u8 -> Flags -> u8 should preserve value
-        let flags: TestFlags = bits.into();
-        let result: u8 = flags.into();
+            /// Verify intersects correctly checks for any common bits.
+            ///
+            /// Property: intersects(other) is true iff (self & other) != 0.
+            #[kani::proof]
+            fn proof_intersects_correctness() {
+                let a = kani::any();
+                let b = kani::any();
 
-        assert_eq!(result, bits);
-    }
+                let flags_a = <$ty>::from_bits_retain(a);
+                let flags_b = <$ty>::from_bits_retain(b);
 
-    /// Verify intersects correctly checks for any common bits.
-    ///
-    /// Property: intersects(other) is true iff (self & other) != 0.
-    #[kani::proof]
-    fn proof_intersects_correctness() {
-        let a: u8 = kani::any();
-        let b: u8 = kani::any();
+                let intersects_result = flags_a.intersects(flags_b);
 
-        let flags_a = TestFlags::from_bits_retain(a);
-        let flags_b = TestFlags::from_bits_retain(b);
+                // Verify intersects semantics
+                assert_eq!(intersects_result, (a & b) != 0);
 
-        let intersects_result = flags_a.intersects(flags_b);
+                // Cover edge cases
+                kani::cover!(intersects_result, "intersects returns true");
+                kani::cover!(!intersects_result, "intersects returns false");
+            }
 
-        // Verify intersects semantics
-        assert_eq!(intersects_result, (a & b) != 0);
+            /// Verify is_empty and is_all are correct.
+            #[kani::proof]
+            fn proof_empty_all_correctness() {
+                let bits = kani::any();
+                let flags = <$ty>::from_bits_retain(bits);
+                let all_flags = <$ty>::all().bits();
+
+                // is_empty is true iff bits == 0
+                assert_eq!(flags.is_empty(), bits == 0);
+
+                // is_all is true iff bits == all_flags
+                assert_eq!(flags.is_all(), bits == all_flags);
+
+                // is_all_known is true iff (bits & all_flags) == all_flags
+                assert_eq!(flags.is_all_known(), (bits & all_flags) == all_flags);
+
+                // Cover all cases
+                kani::cover!(flags.is_empty(), "is_empty returns true");
+                kani::cover!(flags.is_all(), "is_all returns true");
+                kani::cover!(flags.is_all_known(), "is_all_known returns true");
+                kani::cover!(
+                    !flags.is_empty() && !flags.is_all() && !flags.is_all_known(),
+                    "neither empty nor all nor all_known"
+                );
+            }
 
-        // Cover edge cases
-        kani::cover!(intersects_result, "intersects returns true");
-        kani::cover!(!intersects_result, "intersects returns false");
-    }
+            /// Verify algebraic properties of bitwise operations.
+            ///
+            /// Property: Union is commutative: a | b == b | a
+            /// Property: Intersection is commutative: a & b == b & a
+            /// Property: Symmetric difference is commutative: a ^ b == b ^ a
+            #[kani::proof]
+            fn proof_commutative_properties() {
+                let a = kani::any();
+                let b = kani::any();
+
+                let flags_a = <$ty>::from_bits_retain(a);
+                let flags_b = <$ty>::from_bits_retain(b);
+
+                // Union is commutative
+                assert_eq!(flags_a.union(flags_b).bits(), flags_b.union(flags_a).bits());
+
+                // Intersection is commutative
+                assert_eq!(
+                    flags_a.intersection(flags_b).bits(),
+                    flags_b.intersection(flags_a).bits()
+                );
+
+                // Symmetric difference is commutative
+                assert_eq!(
+                    flags_a.symmetric_difference(flags_b).bits(),
+                    flags_b.symmetric_difference(flags_a).bits()
+                );
+            }
 
-    /// Verify is_empty and is_all are correct.
-    #[kani::proof]
-    fn proof_empty_all_correctness() {
-        let bits: u8 = kani::any();
-        let flags = TestFlags::from_bits_retain(bits);
-        let all_flags = TestFlags::all().bits();
+            /// Verify De Morgan's laws hold for complement operations.
+            ///
+            /// Property: !(a | b) == !a & !b
+            /// Property: !(a & b) == !a | !b
+            #[kani::proof]
+            fn proof_de_morgan_laws() {
+                let a = kani::any();
+                let b = kani::any();
+
+                let flags_a = <$ty>::from_bits_retain(a);
+                let flags_b = <$ty>::from_bits_retain(b);
+
+                // !(a | b) == !a & !b
+                let lhs1 = flags_a.union(flags_b).complement();
+                let rhs1 = flags_a.complement().intersection(flags_b.complement());
+                assert_eq!(lhs1.bits(), rhs1.bits());
+
+                // !(a & b) == !a | !b
+                let lhs2 = flags_a.intersection(flags_b).complement();
+                let rhs2 = flags_a.complement().union(flags_b.complement());
+                assert_eq!(lhs2.bits(), rhs2.bits());
+            }
 
-        // is_empty is true iff bits == 0
-        assert_eq!(flags.is_empty(), bits == 0);
+            /// Verify that insert operation never panics and produces correct results.
+            ///
+            /// Property: After insert(other), self contains all bits from other.
+            #[kani::proof]
+            fn proof_insert_correctness() {
+                let initial = kani::any();
+                let to_insert = kani::any();
 
-        // is_all is true iff bits == all_flags
-        assert_eq!(flags.is_all(), bits == all_flags);
+                let mut flags = <$ty>::from_bits_retain(initial);
+                let insert_flags = <$ty>::from_bits_retain(to_insert);
 
-        // is_all_known is true iff (bits & all_flags) == all_flags
-        assert_eq!(flags.is_all_known(), (bits & all_flags) == all_flags);
+                flags.insert(insert_flags);
 
-        // Cover all cases
-        kani::cover!(flags.is_empty(), "is_empty returns true");
-        kani::cover!(flags.is_all(), "is_all returns true");
-        kani::cover!(flags.is_all_known(), "is_all_known returns true");
-        kani::cover!(
-            !flags.is_empty() && !flags.is_all() && !flags.is_all_known(),
-            "neither empty nor all nor all_known"
-        );
-    }
+                // After insert, result should be the union
+                assert_eq!(flags.bits(), initial | to_insert);
 
-    /// Verify algebraic properties of bitwise operations.
-    ///
-    /// Property: Union is commutative: a | b == b | a
-    /// Property: Intersection is commutative: a & b == b & a
-    /// Property: Symmetric difference is commutative: a ^ b == b ^ a
-    #[kani::proof]
-    fn proof_commutative_properties() {
-        let a: u8 = kani::any();
-        let b: u8 = kani::any();
-
-        let flags_a = TestFlags::from_bits_retain(a);
-        let flags_b = TestFlags::from_bits_retain(b);
-
-        // Union is commutative
-        assert_eq!(flags_a.union(flags_b).bits(), flags_b.union(flags_a).bits());
-
-        // Intersection is commutative
-        assert_eq!(
-            flags_a.intersection(flags_b).bits(),
-            flags_b.intersection(flags_a).bits()
-        );
-
-        // Symmetric difference is commutative
-        assert_eq!(
-            flags_a.symmetric_difference(flags_b).bits(),
-            flags_b.symmetric_difference(flags_a).bits()
-        );
-    }
-
-    /// Verify De Morgan's laws hold for complement operations.
-    ///
-    /// Property: !(a | b) == !a & !b
-    /// Property: !(a & b) == !a | !b
-    #[kani::proof]
-    fn proof_de_morgan_laws() {
-        let a: u8 = kani::any();
-        let b: u8 = kani::any();
+                // All bits from to_insert should be present
+                assert!((flags.bits() & to_insert) == to_insert);
+            }
 
-        let flags_a = TestFlags::from_bits_retain(a);
-        let flags_b = TestFlags::from_bits_retain(b);
+            /// Verify that remove operation never panics and produces correct results.
+            ///
+            /// Property: After remove(other), self contains no bits from other.
+            #[kani::proof]
+            fn proof_remove_correctness() {
+                let initial = kani::any();
+                let to_remove = kani::any();
 
-        // !(a | b) == !a & !b
-        let lhs1 = flags_a.union(flags_b).complement();
-        let rhs1 = flags_a.complement().intersection(flags_b.complement());
-        assert_eq!(lhs1.bits(), rhs1.bits());
+                let mut flags = <$ty>::from_bits_retain(initial);
+                let remove_flags = <$ty>::from_bits_retain(to_remove);
 
-        // !(a & b) == !a | !b
-        let lhs2 = flags_a.intersection(flags_b).complement();
-        let rhs2 = flags_a.complement().union(flags_b.complement());
-        assert_eq!(lhs2.bits(), rhs2.bits());
-    }
+                flags.remove(remove_flags);
 
-    /// Verify that insert operation never panics and produces correct results.
-    ///
-    /// Property: After insert(other), self contains all bits from other.
-    #[kani::proof]
-    fn proof_insert_correctness() {
-        let initial: u8 = kani::any();
-        let to_insert: u8 = kani::any();
+                // After remove, result should be the difference
+                assert_eq!(flags.bits(), initial & !to_remove);
 
-        let mut flags = TestFlags::from_bits_retain(initial);
-        let insert_flags = TestFlags::from_bits_retain(to_insert);
+                // No bits from to_remove should be present
+                assert!((flags.bits() & to_remove) == 0);
+            }
 
-        flags.insert(insert_flags);
+            /// Verify that toggle operation never panics and produces correct results.
+            ///
+            /// Property: toggle(other) flips all bits in other.
+            /// Property: Toggling twice restores the original value.
+            #[kani::proof]
+            fn proof_toggle_correctness() {
+                let initial = kani::any();
+                let to_toggle = kani::any();
+
+                let mut flags = <$ty>::from_bits_retain(initial);
+                let toggle_flags = <$ty>::from_bits_retain(to_toggle);
+
+                // First toggle
+                flags.toggle(toggle_flags);
+                assert_eq!(flags.bits(), initial ^ to_toggle);
+
+                // Second toggle should restore original
+                flags.toggle(toggle_flags);
+                assert_eq!(flags.bits(), initial);
+            }
 
-        // After insert, result should be the union
-        assert_eq!(flags.bits(), initial | to_insert);
+            /// Verify that bitwise operator overloads match their method equivalents.
+            ///
+            /// Property: Operators should behave identically to their method counterparts.
+            #[kani::proof]
+            fn proof_operator_overloads() {
+                let a = kani::any();
+                let b = kani::any();
+
+                let flags_a = <$ty>::from_bits_retain(a);
+                let flags_b = <$ty>::from_bits_retain(b);
+
+                // BitOr (|) should match union
+                assert_eq!((flags_a | flags_b).bits(), flags_a.union(flags_b).bits());
+
+                // BitAnd (&) should match intersection
+                assert_eq!(
+                    (flags_a & flags_b).bits(),
+                    flags_a.intersection(flags_b).bits()
+                );
+
+                // BitXor (^) should match symmetric_difference
+                assert_eq!(
+                    (flags_a ^ flags_b).bits(),
+                    flags_a.symmetric_difference(flags_b).bits()
+                );
+
+                // Sub (-) should match difference
+                assert_eq!(
+                    (flags_a - flags_b).bits(),
+                    flags_a.difference(flags_b).bits()
+                );
+
+                // Not (!) should match exactly the form the `complement` option
+                // selects; `proof_complement_no_panic` pins down `complement()`
+                let expected = match <$ty as $crate::Flags>::__NEOBIT_COMPLEMENT {
+                    $crate::__private::Complement::Known => flags_a.complement_known(),
+                    _ => flags_a.complement(),
+                };
+                assert_eq!((!flags_a).bits(), expected.bits());
+            }
 
-        // All bits from to_insert should be present
-        assert!((flags.bits() & to_insert) == to_insert);
-    }
+            /// Verify that complement_known only produces defined flags.
+            ///
+            /// Property: complement_known(a) == !a & all
+            /// Property: a and complement_known(a) partition all()
+            #[kani::proof]
+            fn proof_complement_known_correctness() {
+                let a = kani::any();
+                let flags = <$ty>::from_bits_retain(a);
+                let all_flags = <$ty>::all().bits();
+
+                let result = flags.complement_known();
+
+                assert_eq!(result.bits(), !a & all_flags);
+                assert!(<$ty>::from_bits(result.bits()).is_some());
+                assert_eq!(flags.union(result).bits() & all_flags, all_flags);
+                assert!(!flags.intersects(result));
+            }
 
-    /// Verify that remove operation never panics and produces correct results.
-    ///
-    /// Property: After remove(other), self contains no bits from other.
-    #[kani::proof]
-    fn proof_remove_correctness() {
-        let initial: u8 = kani::any();
-        let to_remove: u8 = kani::any();
+            /// Verify that `field()` and `with_field()` are inverses.
+            ///
+            /// Property: Writing back a field read through the same mask is a
+            /// no-op, and a successful write only changes bits inside the mask.
+            #[kani::proof]
+            fn proof_field_roundtrip() {
+                let a = kani::any();
+                let m = kani::any();
+                let v = kani::any();
+                let flags = <$ty>::from_bits_retain(a);
+                let mask = <$ty>::from_bits_retain(m);
+
+                let same = flags.with_field(mask, flags.field(mask));
+                assert_eq!(same.map(|f| f.bits()), Some(a));
+
+                if let Some(result) = flags.with_field(mask, v) {
+                    assert_eq!(result.field(mask), v);
+                    assert_eq!(
+                        result.difference(mask).bits(),
+                        flags.difference(mask).bits()
+                    );
+                }
+            }
 
-        let mut flags = TestFlags::from_bits_retain(initial);
-        let remove_flags = TestFlags::from_bits_retain(to_remove);
+            /// Verify that assignment operators work correctly.
+            ///
+            /// Property: Assignment operators should modify in place correctly.
+            #[kani::proof]
+            fn proof_assignment_operators() {
+                let initial = kani::any();
+                let other = kani::any();
+
+                let initial_flags = <$ty>::from_bits_retain(initial);
+                let other_flags = <$ty>::from_bits_retain(other);
+
+                // BitOrAssign (|=)
+                let mut flags = initial_flags;
+                flags |= other_flags;
+                assert_eq!(flags.bits(), initial | other);
+
+                // BitAndAssign (&=)
+                let mut flags = initial_flags;
+                flags &= other_flags;
+                assert_eq!(flags.bits(), initial & other);
+
+                // BitXorAssign (^=)
+                let mut flags = initial_flags;
+                flags ^= other_flags;
+                assert_eq!(flags.bits(), initial ^ other);
+
+                // SubAssign (-=)
+                let mut flags = initial_flags;
+                flags -= other_flags;
+                assert_eq!(flags.bits(), initial & !other);
+            }
+        }
+    };
+    ($ty:ty) => {
+        $crate::neobit_kani_proofs! { mod neobit_kani_proofs: $ty }
+    };
+}
 
-        flags.remove(remove_flags);
+/// Generates Kani proof harnesses for a type defined with [`neobit!`].
+///
+/// The harnesses cover the same properties the crate verifies for itself:
+/// operation semantics, `from_bits` and `from_bits_truncate` soundness,
+/// `contains`/`intersects` correctness, `set` behaviour, De Morgan's laws,
+/// operator overloads and roundtrip conversion through `From`.
+///
+/// The harnesses only exist when neobit itself is built under `cfg(kani)`,
+/// which `cargo kani` sets for every crate in the build. Outside of Kani the
+/// macro expands to nothing. The harnesses are emitted in
+/// `mod neobit_kani_proofs`; name the module with `mod name: Type` to verify
+/// several types side by side.
+///
+/// # Example
+///
+/// ```rust
+/// use neobit::neobit;
+///
+/// neobit! {
+///     pub struct Register: u32 {
+///         const READY = 1 << 0;
+///         const ERROR = 1 << 1;
+///     }
+/// }
+///
+/// neobit! {
+///     pub struct Counter: i64 {
+///         const OVERFLOW = 1 << 63;
+///     }
+/// }
+///
+/// neobit::neobit_kani_proofs!(mod register_proofs: Register);
+/// neobit::neobit_kani_proofs!(mod counter_proofs: Counter);
+/// ```
+///
+/// Run them with `cargo kani`.
+#[cfg(not(kani))]
+#[macro_export]
+macro_rules! neobit_kani_proofs {
+    (mod $module:ident: $ty:ty) => {};
+    ($ty:ty) => {};
+}
 
-        // After remove, result should be the difference
-        assert_eq!(flags.bits(), initial & !to_remove);
+/// Generates `#[test]` functions checking the laws every type defined with
+/// [`neobit!`] must follow.
+///
+/// The tests run over edge bit patterns, every single bit and every flag,
+/// unknown bits included, and check:
+///
+/// - `bits()`/`from_bits_retain()` and `From`/`Into` roundtrips
+/// - `From<int>` or `TryFrom<int>` keeps known values and retains, truncates
+///   or rejects unknown bits as set by the `unknown` option
+/// - `from_bits()` and `from_bits_truncate()` against `all()`
+/// - operators and assignment operators against `union()`, `intersection()`,
+///   `difference()`, `symmetric_difference()` and the complement selected
+///   by the `complement` option
+/// - `contains()`, `intersects()`, `insert()`, `remove()`, `toggle()` and
+///   `set()` against the bit operations
+/// - `Debug` of each single-bit flag shows its name and matches
+///   `write_names()`
+/// - `Hash` agrees with `Eq`
+///
+/// The tests are emitted in `mod neobit_test_suite` under `#[cfg(test)]`;
+/// name the module with `mod name: Type` to test several types side by
+/// side. The tests follow the `complement`, `no_from`, `unknown` and
+/// `debug = "custom"` options the type was defined with, so the type is all
+/// they need. The
+/// type must implement `Debug` for the assertion messages.
+///
+/// ```rust
+/// use neobit::{neobit, neobit_test_suite};
+///
+/// neobit! {
+///     pub struct Permissions: u8 {
+///         const READ  = 0b001;
+///         const WRITE = 0b010;
+///     }
+/// }
+///
+/// neobit! {
+///     #[neobit(no_from, debug = "custom")]
+///     pub struct Status: u16 {
+///         const READY = 1;
+///     }
+/// }
+/// # impl core::fmt::Debug for Status {
+/// #     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result { write!(f, "{}", self.bits()) }
+/// # }
+///
+/// neobit_test_suite!(Permissions);
+/// neobit_test_suite!(mod status_laws: Status);
+/// ```
+///
+/// The generated tests need no dependency besides neobit.
+#[macro_export]
+macro_rules! neobit_test_suite {
+    (mod $module:ident: $ty:ty) => {
+        #[cfg(test)]
+        #[allow(non_snake_case, deprecated)]
+        mod $module {
+            #[allow(unused_imports)]
+            use super::*;
+
+            type Bits = <$ty as $crate::Flags>::Bits;
+
+            fn samples() -> impl Iterator<Item = $ty> + Clone {
+                $crate::__private::suite_samples::<$ty>()
+            }
 
-        // No bits from to_remove should be present
-        assert!((flags.bits() & to_remove) == 0);
-    }
+            #[test]
+            fn bits_roundtrip() {
+                for value in samples() {
+                    assert_eq!(<$ty>::from_bits_retain(value.bits()), value);
+                    let bits: Bits = value.into();
+                    assert!(
+                        bits == value.bits(),
+                        "`Into` disagrees with `bits()` for {:?}",
+                        value
+                    );
+                }
+            }
 
-    /// Verify that toggle operation never panics and produces correct results.
-    ///
-    /// Property: toggle(other) flips all bits in other.
-    /// Property: Toggling twice restores the original value.
-    #[kani::proof]
-    fn proof_toggle_correctness() {
-        let initial: u8 = kani::any();
-        let to_toggle: u8 = kani::any();
+            #[test]
+            fn from_roundtrip() {
+                if !<$ty as $crate::Flags>::__NEOBIT_FROM {
+                    return;
+                }
+                for value in samples().filter(|value| <$ty>::all().contains(*value)) {
+                    let converted = <$ty as $crate::Flags>::__neobit_from_bits(value.bits());
+                    assert!(
+                        converted == ::core::option::Option::Some(value),
+                        "`From` changes {:?}",
+                        value
+                    );
+                }
+            }
 
-        let mut flags = TestFlags::from_bits_retain(initial);
-        let toggle_flags = TestFlags::from_bits_retain(to_toggle);
+            #[test]
+            fn from_follows_unknown_policy() {
+                if !<$ty as $crate::Flags>::__NEOBIT_FROM {
+                    return;
+                }
+                let all = <$ty>::all();
+                for value in samples() {
+                    let converted = <$ty as $crate::Flags>::__neobit_from_bits(value.bits());
+                    let expected = match <$ty as $crate::Flags>::__NEOBIT_UNKNOWN {
+                        $crate::__private::Unknown::Retain => ::core::option::Option::Some(value),
+                        $crate::__private::Unknown::Truncate => {
+                            ::core::option::Option::Some(value.intersection(all))
+                        }
+                        $crate::__private::Unknown::Reject if all.contains(value) => {
+                            ::core::option::Option::Some(value)
+                        }
+                        $crate::__private::Unknown::Reject => ::core::option::Option::None,
+                    };
+                    assert!(
+                        converted == expected,
+                        "conversion of {:?} ignores the `unknown` option",
+                        value
+                    );
+                }
+            }
 
-        // First toggle
-        flags.toggle(toggle_flags);
-        assert_eq!(flags.bits(), initial ^ to_toggle);
+            #[test]
+            fn from_bits_against_all() {
+                let all = <$ty>::all();
+                for value in samples() {
+                    let bits = value.bits();
+                    let truncated = <$ty>::from_bits_truncate(bits);
+                    assert_eq!(truncated, value.intersection(all));
+                    assert!(all.contains(truncated));
+                    assert_eq!(<$ty>::from_bits_truncate(truncated.bits()), truncated);
+                    match <$ty>::from_bits(bits) {
+                        ::core::option::Option::Some(exact) => {
+                            assert_eq!(exact, value);
+                            assert!(all.contains(value));
+                        }
+                        ::core::option::Option::None => assert!(!all.contains(value)),
+                    }
+                }
+                assert!(<$ty>::empty().is_empty());
+                assert!(all.is_all());
+            }
 
-        // Second toggle should restore original
-        flags.toggle(toggle_flags);
-        assert_eq!(flags.bits(), initial);
-    }
+            #[test]
+            fn operators_agree_with_methods() {
+                let complement = <$ty as $crate::Flags>::__NEOBIT_COMPLEMENT;
+                for a in samples() {
+                    let raw = <$ty>::from_bits_retain(!a.bits());
+                    let expected = match complement {
+                        $crate::__private::Complement::Masked => raw.intersection(<$ty>::all()),
+                        _ => raw,
+                    };
+                    assert!(
+                        a.complement() == expected,
+                        "`complement()` does not follow the `complement` option for {:?}",
+                        a
+                    );
+                    let expected = match complement {
+                        $crate::__private::Complement::Known => a.complement_known(),
+                        _ => a.complement(),
+                    };
+                    assert!(
+                        !a == expected,
+                        "`!` does not follow the `complement` option for {:?}",
+                        a
+                    );
+                    assert_eq!(
+                        a.complement_known(),
+                        a.complement().intersection(<$ty>::all())
+                    );
+
+                    for b in samples() {
+                        assert_eq!(a | b, a.union(b));
+                        assert_eq!(a & b, a.intersection(b));
+                        assert_eq!(a ^ b, a.symmetric_difference(b));
+                        assert_eq!(a - b, a.difference(b));
+
+                        let mut c = a;
+                        c |= b;
+                        assert_eq!(c, a | b);
+                        let mut c = a;
+                        c &= b;
+                        assert_eq!(c, a & b);
+                        let mut c = a;
+                        c ^= b;
+                        assert_eq!(c, a ^ b);
+                        let mut c = a;
+                        c -= b;
+                        assert_eq!(c, a - b);
+                    }
+                }
+            }
 
-    /// Verify that bitwise operator overloads match their method equivalents.
-    ///
-    /// Property: Operators should behave identically to their method counterparts.
-    #[kani::proof]
-    fn proof_operator_overloads() {
-        let a: u8 = kani::any();
-        let b: u8 = kani::any();
+            #[test]
+            fn membership_and_mutation() {
+                for a in samples() {
+                    for b in samples() {
+                        assert_eq!(a.contains(b), a & b == b);
+                        assert_eq!(a.intersects(b), !(a & b).is_empty());
+
+                        let mut c = a;
+                        c.insert(b);
+                        assert_eq!(c, a | b);
+                        c.remove(b);
+                        assert_eq!(c, a - b);
+                        c.toggle(b);
+                        assert_eq!(c, (a - b) ^ b);
+                        c.set(b, true);
+                        assert!(c.contains(b));
+                        c.set(b, false);
+                        assert!(!c.intersects(b));
+                    }
+                }
+            }
 
-        let flags_a = TestFlags::from_bits_retain(a);
-        let flags_b = TestFlags::from_bits_retain(b);
+            #[test]
+            fn debug_names_flags() {
+                use ::core::fmt::Write;
 
-        // BitOr (|) should match union
-        assert_eq!((flags_a | flags_b).bits(), flags_a.union(flags_b).bits());
+                if !<$ty as $crate::Flags>::__NEOBIT_DEBUG {
+                    return;
+                }
 
-        // BitAnd (&) should match intersection
-        assert_eq!(
-            (flags_a & flags_b).bits(),
-            flags_a.intersection(flags_b).bits()
-        );
+                for &(name, flag) in <$ty as $crate::Flags>::FLAGS {
+                    let bits = $crate::Flags::__neobit_to_u128(flag);
+                    if !bits.is_power_of_two() {
+                        continue;
+                    }
+                    let mut buf = [0u8; 512];
+                    let mut writer = $crate::__private::BufWriter::new(&mut buf);
+                    write!(writer, "{:?}", flag).expect("`Debug` output fits in 512 bytes");
+                    let debug = writer.finish();
+                    assert!(debug.contains(name), "`{}` is missing from {}", name, debug);
+
+                    let mut buf = [0u8; 512];
+                    assert_eq!(
+                        flag.write_names(&mut buf),
+                        ::core::result::Result::Ok(debug)
+                    );
+                }
+            }
 
-        // BitXor (^) should match symmetric_difference
-        assert_eq!(
-            (flags_a ^ flags_b).bits(),
-            flags_a.symmetric_difference(flags_b).bits()
-        );
+            #[test]
+            fn hash_agrees_with_eq() {
+                for a in samples() {
+                    let copy = <$ty>::from_bits_retain(a.bits());
+                    assert_eq!(
+                        $crate::__private::suite_hash(&a),
+                        $crate::__private::suite_hash(&copy)
+                    );
+                    for b in samples() {
+                        if a == b {
+                            assert_eq!(
+                                $crate::__private::suite_hash(&a),
+                                $crate::__private::suite_hash(&b)
+                            );
+                        }
+                        assert_eq!(a == b, a.bits() == b.bits());
+                    }
+                }
+            }
+        }
+    };
+    ($ty:ty) => {
+        $crate::neobit_test_suite! { mod neobit_test_suite: $ty }
+    };
+}
 
-        // Sub (-) should match difference
-        assert_eq!(
-            (flags_a - flags_b).bits(),
-            flags_a.difference(flags_b).bits()
-        );
+#[cfg(kani)]
+mod kani_proofs {
+    //! Kani formal verification proofs for neobit.
+    //!
+    //! These proof harnesses verify that all bitwise operations are panic-free
+    //! and mathematically correct across all possible input combinations.
 
-        // Not (!) should match complement
-        assert_eq!((!flags_a).bits(), flags_a.complement().bits());
+    // Test flags for verification
+    neobit! {
+        /// Flags used for Kani verification proofs
+        pub struct TestFlags: u8 {
+            const A = 0b0001;
+            const B = 0b0010;
+            const C = 0b0100;
+            const D = 0b1000;
+        }
     }
 
-    /// Verify that assignment operators work correctly.
-    ///
-    /// Property: Assignment operators should modify in place correctly.
-    #[kani::proof]
-    fn proof_assignment_operators() {
-        let initial: u8 = kani::any();
-        let other: u8 = kani::any();
-
-        let initial_flags = TestFlags::from_bits_retain(initial);
-        let other_flags = TestFlags::from_bits_retain(other);
-
-        // BitOrAssign (|=)
-        let mut flags = initial_flags;
-        flags |= other_flags;
-        assert_eq!(flags.bits(), initial | other);
-
-        // BitAndAssign (&=)
-        let mut flags = initial_flags;
-        flags &= other_flags;
-        assert_eq!(flags.bits(), initial & other);
-
-        // BitXorAssign (^=)
-        let mut flags = initial_flags;
-        flags ^= other_flags;
-        assert_eq!(flags.bits(), initial ^ other);
-
-        // SubAssign (-=)
-        let mut flags = initial_flags;
-        flags -= other_flags;
-        assert_eq!(flags.bits(), initial & !other);
-    }
+    neobit_kani_proofs!(TestFlags);
 }
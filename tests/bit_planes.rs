@@ -0,0 +1,85 @@
+#![cfg(feature = "alloc")]
+
+use neobit::{neobit, BitPlanes, FlagSliceExt};
+
+neobit! {
+    pub struct Entry: i16 {
+        const DELETED = 0x0001;
+        const PINNED  = 0x0002;
+        const SHARED  = 0x0004;
+        const LOCKED  = i16::MIN;
+    }
+}
+
+/// Values spanning several words, with a partial last word
+fn sample() -> Vec<Entry> {
+    (0..150)
+        .map(|i| {
+            let mut entry = Entry::empty();
+            entry.set(Entry::DELETED, i % 2 == 0);
+            entry.set(Entry::PINNED, i % 7 == 0);
+            entry.set(Entry::LOCKED, i == 149);
+            entry
+        })
+        .collect()
+}
+
+#[test]
+fn test_roundtrip() {
+    let values = sample();
+    let planes = BitPlanes::from(values.as_slice());
+    assert_eq!(planes.len(), 150);
+    assert!(planes.iter().eq(values.iter().copied()));
+    assert_eq!(planes.get(149), Some(Entry::LOCKED));
+    assert_eq!(planes.get(150), None);
+    assert!(BitPlanes::<Entry>::new().is_empty());
+}
+
+#[test]
+fn test_queries_match_slice() {
+    let values = sample();
+    let planes: BitPlanes<Entry> = values.iter().copied().collect();
+
+    let queries = [
+        Entry::empty(),
+        Entry::DELETED,
+        Entry::DELETED | Entry::PINNED,
+        Entry::SHARED,
+        Entry::LOCKED | Entry::PINNED,
+    ];
+    for &query in &queries {
+        assert_eq!(
+            planes.count_containing(query),
+            values.count_containing(query)
+        );
+        assert_eq!(
+            planes.count_intersecting(query),
+            values.count_intersecting(query)
+        );
+        assert!(planes
+            .indices_containing(query)
+            .eq(values.indices_containing(query)));
+        assert!(planes
+            .indices_intersecting(query)
+            .eq(values.indices_intersecting(query)));
+    }
+}
+
+#[test]
+fn test_updates_match_slice() {
+    let mut values = sample();
+    let mut planes = BitPlanes::from(values.as_slice());
+
+    planes.insert_all(Entry::SHARED | Entry::LOCKED);
+    values.insert_all(Entry::SHARED | Entry::LOCKED);
+    planes.remove_all(Entry::DELETED);
+    values.remove_all(Entry::DELETED);
+    planes.set(3, Entry::PINNED);
+    values[3] = Entry::PINNED;
+    planes.push(Entry::DELETED);
+    values.push(Entry::DELETED);
+
+    assert!(planes.iter().eq(values.iter().copied()));
+    assert_eq!(planes.count_containing(Entry::empty()), 151);
+    assert_eq!(planes.count_containing(Entry::SHARED), 149);
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "bitflags-compat")]
+
+use std::collections::HashSet;
+
+neobit::bitflags! {
+    /// Declared exactly as for bitflags 2
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Flags: u32 {
+        const A = 0b0001;
+        const B = 0b0010;
+        const C = 0b0100;
+        const ABC = Self::A.bits() | Self::B.bits() | Self::C.bits();
+    }
+
+    #[derive(Clone, Copy, Default)]
+    #[repr(transparent)]
+    pub struct External: u8 {
+        const READY = 0x01;
+
+        // The hardware may set any bit
+        const _ = !0;
+    }
+}
+
+#[test]
+fn test_declaration() {
+    assert_eq!(Flags::ABC, Flags::A | Flags::B | Flags::C);
+    assert_eq!(Flags::all().bits(), 0b0111);
+    assert_eq!(format!("{:?}", Flags::A | Flags::B), "Flags(A | B)");
+
+    let mut set = HashSet::new();
+    set.insert(Flags::A);
+    assert!(set.contains(&Flags::A));
+    assert_eq!(External::default(), External::empty());
+    assert_eq!(core::mem::size_of::<External>(), 1);
+}
+
+#[test]
+fn test_unnamed_known_bits() {
+    assert_eq!(External::all().bits(), 0xFF);
+    assert_eq!(
+        External::from_bits(0x81),
+        Some(External::from_bits_retain(0x81))
+    );
+    assert_eq!(External::from_bits_truncate(0x81).bits(), 0x81);
+    assert_eq!(<External as neobit::Flags>::FLAG_COUNT, 1);
+    assert_eq!(External::from_name("_"), None);
+    assert_eq!(
+        format!("{:?}", External::from_bits_retain(0x81)),
+        "External(READY | 0x80)"
+    );
+}
+
+#[test]
+fn test_complement_masked() {
+    assert_eq!(!Flags::A, Flags::B | Flags::C);
+    assert_eq!(Flags::A.complement(), Flags::B | Flags::C);
+    assert_eq!(Flags::from_bits_retain(0x100).complement(), Flags::all());
+    assert_eq!((!External::READY).bits(), 0xFE);
+}
+
+#[test]
+fn test_iteration() {
+    let flags = Flags::A | Flags::C | Flags::from_bits_retain(0x100);
+    assert_eq!(
+        flags.iter().collect::<Vec<_>>(),
+        [Flags::A, Flags::C, Flags::from_bits_retain(0x100)]
+    );
+    assert_eq!(
+        Flags::all().iter_names().collect::<Vec<_>>(),
+        [("A", Flags::A), ("B", Flags::B), ("C", Flags::C)]
+    );
+    assert_eq!(Flags::from_name("ABC"), Some(Flags::all()));
+    assert_eq!(Flags::from_name(""), None);
+}
@@ -21,6 +21,17 @@ neobit! {
     }
 }
 
+neobit! {
+    #[neobit(flag_enum = EnumFlag { A, B, C, Bc })]
+    pub struct EnumFlags: u8 {
+        const A = 0b0001;
+        const B = 0b0010;
+        alias FIRST = A;
+        const C = 0b0100;
+        const BC = Self::B.union(Self::C).bits();
+    }
+}
+
 // u16 structs
 neobit! {
     pub struct Flags16: u16 {
@@ -47,6 +58,17 @@ neobit! {
     }
 }
 
+neobit! {
+    pub struct AliasFlags: u32 {
+        alias FSYNC = SYNC;
+        const SYNC = 0x0010;
+        const DSYNC = 0x1000;
+        #[deprecated(note = "use `DSYNC`")]
+        alias O_DSYNC = DSYNC;
+        alias SYNC_ALL = Self::SYNC.union(Self::DSYNC);
+    }
+}
+
 neobit! {
     pub struct SingleFlag: u32 {
         const ONLY = 0x8000_0000;
@@ -100,6 +122,17 @@ neobit! {
     }
 }
 
+neobit! {
+    /// Signed logical set with masked `!`
+    #[neobit(complement = known)]
+    #[repr(transparent)]
+    pub struct KnownComplementFlags: i16 {
+        const A = 0b001;
+        const B = 0b010;
+        const C = 0b100;
+    }
+}
+
 // i128 structs
 neobit! {
     pub struct SignedFlags128: i128 {
@@ -661,3 +694,1666 @@ fn test_signed_flags128_complement() {
     let complement = !flags;
     assert_eq!(complement.bits(), -2i128);
 }
+
+// =============================================================================
+// AliasFlags Tests (aliases)
+// =============================================================================
+
+#[test]
+fn test_alias_flags_same_bits() {
+    assert_eq!(AliasFlags::FSYNC, AliasFlags::SYNC);
+    #[allow(deprecated)]
+    let old = AliasFlags::O_DSYNC;
+    assert_eq!(old, AliasFlags::DSYNC);
+    assert_eq!(AliasFlags::SYNC_ALL.bits(), 0x1010);
+}
+
+#[test]
+fn test_alias_flags_all_is_canonical() {
+    assert_eq!(AliasFlags::all().bits(), 0x1010);
+    assert!(AliasFlags::all().contains(AliasFlags::FSYNC));
+}
+
+#[test]
+fn test_alias_flags_debug_uses_canonical_name() {
+    // The alias is declared before its target but never printed
+    assert_eq!(format!("{:?}", AliasFlags::FSYNC), "AliasFlags(SYNC)");
+    assert_eq!(
+        format!("{:?}", AliasFlags::all()),
+        "AliasFlags(SYNC | DSYNC)"
+    );
+    assert_eq!(
+        format!("{:?}", AliasFlags::SYNC_ALL | AliasFlags::from(0x8000)),
+        "AliasFlags(SYNC | DSYNC | 0x8000)"
+    );
+}
+
+#[test]
+fn test_alias_flags_from_name() {
+    assert_eq!(AliasFlags::from_name("SYNC"), Some(AliasFlags::SYNC));
+    assert_eq!(AliasFlags::from_name("FSYNC"), Some(AliasFlags::SYNC));
+    assert_eq!(AliasFlags::from_name("O_DSYNC"), Some(AliasFlags::DSYNC));
+    assert_eq!(AliasFlags::from_name("SYNC_ALL"), Some(AliasFlags::all()));
+    assert_eq!(AliasFlags::from_name("sync"), None);
+    assert_eq!(AliasFlags::from_name(""), None);
+}
+
+// =============================================================================
+// Complement Semantics Tests
+// =============================================================================
+
+#[test]
+fn test_complement_known() {
+    const NOT_A: Flags8 = Flags8::A.complement_known();
+    assert_eq!(NOT_A, Flags8::B | Flags8::C | Flags8::D);
+
+    // Unknown bits are dropped, and never introduced
+    let with_unknown = Flags8::from_bits_retain(0b1000_0001);
+    assert_eq!(
+        with_unknown.complement_known(),
+        Flags8::B | Flags8::C | Flags8::D
+    );
+    assert_eq!(Flags8::all().complement_known(), Flags8::empty());
+
+    // Raw `!` is unaffected
+    assert_eq!((!Flags8::A).bits(), 0b1111_1110);
+}
+
+#[test]
+fn test_complement_known_signed() {
+    assert_eq!(SignedFlags8::A.complement_known().bits(), 0b0110);
+    assert_eq!(SignedFlags8::A.complement().bits(), -2);
+}
+
+#[test]
+fn test_complement_option_known() {
+    let not_a = !KnownComplementFlags::A;
+    assert_eq!(not_a, KnownComplementFlags::B | KnownComplementFlags::C);
+    assert_eq!(not_a.bits(), 0b110);
+
+    // The const method keeps its raw semantics
+    assert_eq!(KnownComplementFlags::A.complement().bits(), -2);
+
+    // De Morgan holds within the defined flags
+    let a = KnownComplementFlags::A;
+    let b = KnownComplementFlags::B;
+    assert_eq!(!(a | b), !a & !b);
+    assert_eq!(!(a & b), !a | !b);
+    assert_eq!(!!a, a);
+}
+
+#[test]
+fn test_complement_option_keeps_struct_attributes() {
+    assert_eq!(
+        core::mem::size_of::<KnownComplementFlags>(),
+        core::mem::size_of::<i16>()
+    );
+    assert_eq!(
+        format!("{:?}", !KnownComplementFlags::empty()),
+        "KnownComplementFlags(A | B | C)"
+    );
+}
+
+// =============================================================================
+// EnumFlags Tests (companion enum)
+// =============================================================================
+
+#[test]
+fn test_flag_enum_variants() {
+    assert_eq!(
+        EnumFlag::ALL_VARIANTS,
+        &[EnumFlag::A, EnumFlag::B, EnumFlag::C, EnumFlag::Bc]
+    );
+    assert_eq!(EnumFlag::B.name(), "B");
+    assert_eq!(EnumFlag::Bc.name(), "BC");
+    const C: EnumFlags = EnumFlag::C.flag();
+    assert_eq!(C, EnumFlags::C);
+}
+
+#[test]
+fn test_flag_enum_exhaustive_match() {
+    fn label(flag: EnumFlag) -> &'static str {
+        match flag {
+            EnumFlag::A => "a",
+            EnumFlag::B => "b",
+            EnumFlag::C => "c",
+            EnumFlag::Bc => "bc",
+        }
+    }
+    let labels: Vec<_> = EnumFlag::ALL_VARIANTS.iter().map(|&f| label(f)).collect();
+    assert_eq!(labels, ["a", "b", "c", "bc"]);
+}
+
+#[test]
+fn test_flag_enum_conversions() {
+    use core::convert::TryFrom;
+
+    assert_eq!(EnumFlags::from(EnumFlag::A), EnumFlags::A);
+    let flags: EnumFlags = EnumFlag::Bc.into();
+    assert_eq!(flags, EnumFlags::B | EnumFlags::C);
+
+    assert_eq!(EnumFlag::try_from(EnumFlags::FIRST), Ok(EnumFlag::A));
+    assert_eq!(EnumFlag::try_from(EnumFlags::BC), Ok(EnumFlag::Bc));
+    assert_eq!(
+        EnumFlag::try_from(EnumFlags::A | EnumFlags::B),
+        Err(EnumFlags::A | EnumFlags::B)
+    );
+    assert_eq!(
+        EnumFlag::try_from(EnumFlags::empty()),
+        Err(EnumFlags::empty())
+    );
+    assert_eq!(
+        EnumFlag::try_from(EnumFlags::from(0x80)),
+        Err(EnumFlags::from(0x80))
+    );
+}
+
+#[test]
+fn test_flag_enum_iter_variants() {
+    let flags = EnumFlags::A | EnumFlags::C | EnumFlags::from(0x80);
+    let variants: Vec<_> = flags.iter_variants().collect();
+    assert_eq!(variants, [EnumFlag::A, EnumFlag::C]);
+
+    // BC is fully covered by B and C, so it is not yielded again
+    let variants: Vec<_> = EnumFlags::all().iter_variants().collect();
+    assert_eq!(variants, [EnumFlag::A, EnumFlag::B, EnumFlag::C]);
+
+    assert_eq!(EnumFlags::empty().iter_variants().count(), 0);
+}
+
+// =============================================================================
+// Metadata Constants Tests
+// =============================================================================
+
+#[test]
+fn test_flags_table() {
+    assert_eq!(
+        Flags8::FLAGS,
+        &[
+            ("A", Flags8::A),
+            ("B", Flags8::B),
+            ("C", Flags8::C),
+            ("D", Flags8::D)
+        ]
+    );
+    assert_eq!(Flags8::FLAG_COUNT, 4);
+
+    // Aliases are excluded, composite constants are not
+    let names: Vec<_> = AliasFlags::FLAGS.iter().map(|&(name, _)| name).collect();
+    assert_eq!(names, ["SYNC", "DSYNC"]);
+    assert_eq!(Flags32::FLAG_COUNT, 6);
+
+    assert!(EmptyFlags::FLAGS.is_empty());
+    assert_eq!(EmptyFlags::FLAG_COUNT, 0);
+}
+
+#[test]
+fn test_flags_table_const_context() {
+    // Build a compile-time lookup table from the metadata
+    const NAMES: [&str; Flags8::FLAG_COUNT] = {
+        let mut names = [""; Flags8::FLAG_COUNT];
+        let mut i = 0;
+        while i < Flags8::FLAG_COUNT {
+            names[i] = Flags8::FLAGS[i].0;
+            i += 1;
+        }
+        names
+    };
+    assert_eq!(NAMES, ["A", "B", "C", "D"]);
+}
+
+#[test]
+fn test_all_and_type_names() {
+    const ALL: Flags8 = Flags8::ALL;
+    assert_eq!(ALL, Flags8::all());
+    assert_eq!(AliasFlags::ALL.bits(), 0x1010);
+
+    assert_eq!(Flags8::REPR_NAME, "u8");
+    assert_eq!(SignedFlags128::REPR_NAME, "i128");
+    assert_eq!(Flags8::TYPE_NAME, "Flags8");
+    assert_eq!(KnownComplementFlags::TYPE_NAME, "KnownComplementFlags");
+}
+
+neobit! {
+    pub struct MetadataFlags: u16 {
+        const LOW = 0x00FF;
+        const HIGH = 0xFF00;
+    }
+}
+
+neobit! {
+    // With `no_metadata`, the names are free for flags and user constants
+    #[neobit(no_metadata)]
+    pub struct NamedAll: u8 {
+        const FLAGS = 0b01;
+        const ALL = 0b11;
+        #[ro]
+        const RO_MASK = 0b100;
+    }
+}
+
+impl NamedAll {
+    pub const TYPE_NAME: &'static str = "custom";
+}
+
+#[test]
+fn test_inherent_metadata() {
+    // Inherent constants, no trait import needed
+    const COUNT: usize = MetadataFlags::FLAG_COUNT;
+    assert_eq!(COUNT, 2);
+    assert_eq!(
+        MetadataFlags::FLAGS,
+        &[("LOW", MetadataFlags::LOW), ("HIGH", MetadataFlags::HIGH)]
+    );
+    assert_eq!(MetadataFlags::ALL, MetadataFlags::all());
+    assert_eq!(MetadataFlags::REPR_NAME, "u16");
+    assert_eq!(MetadataFlags::TYPE_NAME, "MetadataFlags");
+    assert_eq!(
+        MetadataFlags::FLAGS,
+        <MetadataFlags as neobit::Flags>::FLAGS
+    );
+}
+
+#[test]
+fn test_no_metadata_option() {
+    assert_eq!(NamedAll::ALL.bits(), 0b11);
+    assert_eq!(NamedAll::TYPE_NAME, "custom");
+    assert_eq!(<NamedAll as neobit::Flags>::TYPE_NAME, "NamedAll");
+    assert_eq!(<NamedAll as neobit::Flags>::ALL, NamedAll::all());
+    assert_ne!(<NamedAll as neobit::Flags>::ALL, NamedAll::ALL);
+    assert_eq!(<NamedAll as neobit::Flags>::RO_MASK, NamedAll::RO_MASK);
+    assert_eq!(format!("{:?}", NamedAll::ALL), "NamedAll(FLAGS | 0x2)");
+}
+
+// =============================================================================
+// write_names Tests (allocation-free rendering)
+// =============================================================================
+
+#[test]
+fn test_write_names_matches_debug() {
+    let mut buf = [0u8; 64];
+
+    let values = [
+        Flags8::empty(),
+        Flags8::A,
+        Flags8::all(),
+        Flags8::from(0b1000_0001),
+        Flags8::from(0b1000_0000),
+    ];
+    for value in values {
+        assert_eq!(value.write_names(&mut buf).unwrap(), format!("{:?}", value));
+    }
+
+    let signed = SignedFlags8::from(-1);
+    assert_eq!(
+        signed.write_names(&mut buf).unwrap(),
+        "SignedFlags8(A | B | C | 0xf8)"
+    );
+    assert_eq!(
+        signed.write_names(&mut buf).unwrap(),
+        format!("{:?}", signed)
+    );
+
+    let wide = SignedFlags128::A | SignedFlags128::NEG;
+    assert_eq!(wide.write_names(&mut buf).unwrap(), format!("{:?}", wide));
+}
+
+#[test]
+fn test_write_names_buffer_too_small() {
+    let mut buf = [0u8; 12];
+    assert_eq!(Flags8::A.write_names(&mut buf), Ok("Flags8(A)"));
+    assert_eq!(
+        Flags8::all().write_names(&mut buf),
+        Err(neobit::BufferTooSmall)
+    );
+    assert_eq!(
+        Flags8::empty().write_names(&mut []),
+        Err(neobit::BufferTooSmall)
+    );
+}
+
+// =============================================================================
+// Bit-Field Tests (field / with_field)
+// =============================================================================
+
+#[test]
+fn test_field_extract_and_insert() {
+    let mask = Flags16::from_bits_retain(0b0011_1000);
+    let reg = Flags16::from_bits_retain(0xFF07);
+
+    assert_eq!(reg.field(mask), 0);
+    let reg = reg.with_field(mask, 0b101).unwrap();
+    assert_eq!(reg.bits(), 0xFF2F);
+    assert_eq!(reg.field(mask), 0b101);
+
+    // Bits outside the mask are preserved when the field is rewritten
+    let reg = reg.with_field(mask, 0b010).unwrap();
+    assert_eq!(reg.bits(), 0xFF17);
+}
+
+#[test]
+fn test_with_field_rejects_overflow() {
+    let mask = Flags32::from_bits_retain(0x0000_0F00);
+    assert_eq!(Flags32::empty().with_field(mask, 0x10), None);
+    assert_eq!(
+        Flags32::empty().with_field(mask, 0xF),
+        Some(Flags32::from(0x0F00))
+    );
+    assert_eq!(Flags32::A.with_field(Flags32::empty(), 0), Some(Flags32::A));
+    assert_eq!(Flags32::A.with_field(Flags32::empty(), 1), None);
+}
+
+#[test]
+fn test_field_full_width() {
+    let all = Flags128::from_bits_retain(u128::MAX);
+    let top = Flags128::from_bits_retain(0xFFFF << 112);
+    assert_eq!(all.field(all), u128::MAX);
+    assert_eq!(all.field(top), 0xFFFF);
+    assert_eq!(Flags128::empty().with_field(top, 0xFFFF), Some(top));
+}
+
+#[test]
+fn test_field_signed_no_sign_extension() {
+    let top = SignedFlags8::from_bits_retain(0b1100_0000u8 as i8);
+    let reg = SignedFlags8::from(-1);
+    assert_eq!(reg.field(top), 0b11);
+
+    let reg = SignedFlags8::A.with_field(top, 0b10).unwrap();
+    assert_eq!(reg.bits(), 0b1000_0001u8 as i8);
+    assert_eq!(SignedFlags8::A.with_field(top, -1), None);
+
+    let wide = SignedFlags128::from_bits_retain(i128::MIN);
+    assert_eq!(SignedFlags128::from(-1).field(wide), 1);
+}
+
+#[test]
+fn test_field_const_context() {
+    const MASK: Flags8 = Flags8::from_bits_retain(0b1111_0000);
+    const REG: Flags8 = match Flags8::A.with_field(MASK, 0xA) {
+        Some(reg) => reg,
+        None => Flags8::empty(),
+    };
+    const VALUE: u8 = REG.field(MASK);
+    assert_eq!(REG.bits(), 0xA1);
+    assert_eq!(VALUE, 0xA);
+}
+
+// =============================================================================
+// Register Access Tests (#[w1c], #[ro], #[rc])
+// =============================================================================
+
+neobit! {
+    /// Interrupt status register
+    pub struct IrqStatus: u16 {
+        const ENABLE = 1 << 0;
+        /// Transfer complete
+        #[w1c]
+        const TC = 1 << 1;
+        #[w1c]
+        #[allow(dead_code)]
+        const ERR = 1 << 2;
+        #[ro]
+        const BUSY = 1 << 3;
+        #[rc]
+        #[ro]
+        const DATA_LOST = 1 << 4;
+        alias TRANSFER_COMPLETE = TC;
+    }
+}
+
+#[test]
+fn test_access_masks() {
+    use neobit::Flags;
+
+    assert_eq!(IrqStatus::W1C_MASK, IrqStatus::TC | IrqStatus::ERR);
+    assert_eq!(IrqStatus::RO_MASK, IrqStatus::BUSY | IrqStatus::DATA_LOST);
+    assert_eq!(IrqStatus::RC_MASK, IrqStatus::DATA_LOST);
+    assert!(Flags8::W1C_MASK.is_empty());
+    assert!(Flags8::RO_MASK.is_empty());
+    assert!(Flags8::RC_MASK.is_empty());
+
+    // Markers do not change the flags themselves
+    assert_eq!(IrqStatus::all().bits(), 0b1_1111);
+    assert_eq!(format!("{:?}", IrqStatus::TC), "IrqStatus(TC)");
+}
+
+#[test]
+fn test_write_value_to_clear() {
+    use neobit::Flags;
+
+    assert_eq!(
+        IrqStatus::write_value_to_clear(IrqStatus::TC),
+        IrqStatus::TC
+    );
+    assert_eq!(
+        IrqStatus::write_value_to_clear(IrqStatus::all()),
+        IrqStatus::W1C_MASK
+    );
+    assert!(IrqStatus::write_value_to_clear(IrqStatus::ENABLE | IrqStatus::BUSY).is_empty());
+}
+
+neobit! {
+    /// `IrqStatus` without the `#[ro]` and `#[rc]` markers
+    pub struct IrqStatusW1c: u16 {
+        const ENABLE = 1 << 0;
+        #[w1c]
+        const TC = 1 << 1;
+        #[w1c]
+        const ERR = 1 << 2;
+        const BUSY = 1 << 3;
+        const DATA_LOST = 1 << 4;
+    }
+}
+
+#[test]
+fn test_modify_safe_masks_w1c_and_ro() {
+    let read = IrqStatus::from_bits_retain(0x801E);
+
+    // Writing back unchanged must not clear pending interrupts, and the
+    // read-only BUSY and DATA_LOST are left out
+    let write = read.modify_safe(|_| {});
+    assert_eq!(write.bits(), 0x8000);
+
+    let write = read.modify_safe(|r| r.insert(IrqStatus::ENABLE | IrqStatus::TC | IrqStatus::BUSY));
+    assert_eq!(write.bits(), 0x8001);
+
+    let write = write | IrqStatus::write_value_to_clear(IrqStatus::TRANSFER_COMPLETE);
+    assert_eq!(write.bits(), 0x8003);
+
+    // Without `#[ro]` the same bits are written back
+    let write = IrqStatusW1c::from_bits_retain(0x801E).modify_safe(|_| {});
+    assert_eq!(write.bits(), 0x8018);
+}
+
+#[test]
+fn test_read_clear_flags() {
+    assert!(IrqStatus::read_has_side_effects());
+    assert!(!IrqStatusW1c::read_has_side_effects());
+    assert!(!Flags8::read_has_side_effects());
+
+    let read = IrqStatus::from_bits_retain(0x801E);
+    assert_eq!(read.after_read().bits(), 0x800E);
+    const AFTER: IrqStatus = IrqStatus::DATA_LOST.after_read();
+    assert!(AFTER.is_empty());
+
+    // Without `#[rc]` the read leaves the value as it is
+    let read = IrqStatusW1c::from_bits_retain(0x801E);
+    assert_eq!(read.after_read(), read);
+}
+
+// =============================================================================
+// FFI Constants Module Tests (consts option)
+// =============================================================================
+
+const FFI_SHIFT: u32 = 4;
+
+neobit! {
+    /// Flags exported to C
+    #[neobit(consts = ffi_flags_consts)]
+    pub struct FfiFlags: u16 {
+        /// Ready
+        const READY = 0x01;
+        #[w1c]
+        const ERROR = 1 << FFI_SHIFT;
+        alias FAULT = ERROR;
+        #[deprecated(note = "use `READY`")]
+        alias RDY = READY;
+    }
+}
+
+neobit! {
+    #[repr(transparent)]
+    #[neobit(consts = signed_ffi_consts)]
+    pub struct SignedFfiFlags: i64 {
+        const LOW = 1;
+        const SIGN = i64::MIN;
+    }
+}
+
+#[test]
+fn test_consts_module_values() {
+    let ready: u16 = ffi_flags_consts::READY;
+    assert_eq!(ready, FfiFlags::READY.bits());
+    assert_eq!(ffi_flags_consts::ERROR, 0x10);
+    assert_eq!(ffi_flags_consts::FAULT, FfiFlags::ERROR.bits());
+    #[allow(deprecated)]
+    let rdy = ffi_flags_consts::RDY;
+    assert_eq!(rdy, 0x01);
+
+    assert_eq!(signed_ffi_consts::LOW, 1i64);
+    assert_eq!(signed_ffi_consts::SIGN, SignedFfiFlags::SIGN.bits());
+}
+
+#[test]
+fn test_consts_layout_matches_repr() {
+    assert_eq!(
+        core::mem::size_of::<FfiFlags>(),
+        core::mem::size_of::<u16>()
+    );
+    assert_eq!(
+        core::mem::align_of::<FfiFlags>(),
+        core::mem::align_of::<u16>()
+    );
+    assert_eq!(
+        core::mem::size_of::<SignedFfiFlags>(),
+        core::mem::size_of::<i64>()
+    );
+}
+
+// =============================================================================
+// require / require_any Tests
+// =============================================================================
+
+#[test]
+fn test_require() {
+    let flags = Flags32::A | Flags32::B;
+    assert_eq!(flags.require(Flags32::A), Ok(()));
+    assert_eq!(flags.require(Flags32::empty()), Ok(()));
+
+    let err = flags
+        .require(Flags32::A | Flags32::C | Flags32::D)
+        .unwrap_err();
+    assert_eq!(err, neobit::Missing::all_of(Flags32::C | Flags32::D));
+    assert_eq!(err.flags(), Flags32::C | Flags32::D);
+    assert!(!err.is_any_of());
+    assert_eq!(err.to_string(), "missing flags: C | D");
+    assert_eq!(
+        format!("{:?}", err),
+        "Missing { flags: Flags32(C | D), any: false }"
+    );
+}
+
+#[test]
+fn test_require_any() {
+    let flags = Flags32::A;
+    assert_eq!(flags.require_any(Flags32::A | Flags32::B), Ok(()));
+
+    let err = flags.require_any(Flags32::B | Flags32::C).unwrap_err();
+    assert_eq!(err.flags(), Flags32::B | Flags32::C);
+    assert!(err.is_any_of());
+    assert_eq!(err.to_string(), "missing any of: B | C");
+
+    let err = flags.require_any(Flags32::empty()).unwrap_err();
+    assert_eq!(err.to_string(), "missing any of: empty");
+}
+
+#[test]
+fn test_require_unknown_and_signed() {
+    let err = Flags8::A.require(Flags8::from(0x81)).unwrap_err();
+    assert_eq!(err.to_string(), "missing flags: 0x80");
+
+    let err = SignedFlags32::POSITIVE
+        .require(SignedFlags32::all())
+        .unwrap_err();
+    assert_eq!(err.to_string(), "missing flags: NEGATIVE_BIT");
+}
+
+#[test]
+fn test_require_const_context() {
+    const OK: bool = Flags16::all().require(Flags16::A).is_ok();
+    const ERR: bool = Flags16::A.require(Flags16::all()).is_err();
+    assert!(OK);
+    assert!(ERR);
+}
+
+#[test]
+fn test_flags_trait() {
+    fn names<F: neobit::Flags>() -> Vec<&'static str> {
+        F::FLAGS.iter().map(|&(name, _)| name).collect()
+    }
+    fn roundtrip<F: neobit::Flags>(flags: F) -> F {
+        F::from_bits_retain(neobit::Flags::bits(flags))
+    }
+
+    assert_eq!(names::<Flags8>(), ["A", "B", "C", "D"]);
+    assert_eq!(names::<AliasFlags>(), ["SYNC", "DSYNC"]);
+    assert_eq!(
+        roundtrip(SignedFlags128::from(-5)),
+        SignedFlags128::from(-5)
+    );
+}
+
+// =============================================================================
+// Subset Relation Tests
+// =============================================================================
+
+#[test]
+fn test_subset_relations() {
+    let ab = Flags32::A | Flags32::B;
+
+    assert!(Flags32::A.is_subset(ab));
+    assert!(ab.is_subset(ab));
+    assert!(Flags32::empty().is_subset(Flags32::empty()));
+    assert!(!ab.is_subset(Flags32::A));
+
+    assert!(ab.is_superset(Flags32::B));
+    assert!(!Flags32::A.is_superset(ab));
+
+    assert!(Flags32::A.is_strict_subset(ab));
+    assert!(Flags32::empty().is_strict_subset(Flags32::A));
+    assert!(!ab.is_strict_subset(ab));
+
+    assert!(Flags32::A.is_disjoint(Flags32::B | Flags32::C));
+    assert!(!ab.is_disjoint(Flags32::B));
+
+    // Raw integer order does not imply inclusion
+    assert!(Flags32::B < (Flags32::A | Flags32::C));
+    assert!(!Flags32::B.is_subset(Flags32::A | Flags32::C));
+}
+
+#[test]
+fn test_subset_relations_signed_and_const() {
+    const NEG: SignedFlags32 = SignedFlags32::NEGATIVE_BIT;
+    const SUBSET: bool = NEG.is_subset(SignedFlags32::all());
+    assert!(SUBSET);
+    assert!(SignedFlags32::all().is_superset(NEG));
+    assert!(NEG.is_disjoint(SignedFlags32::POSITIVE));
+}
+
+#[test]
+fn test_subset_ord() {
+    use neobit::SubsetOrd;
+    use std::cmp::Ordering;
+
+    let a = SubsetOrd(Flags8::A);
+    let ab = SubsetOrd(Flags8::A | Flags8::B);
+    let c = SubsetOrd(Flags8::C);
+
+    assert!(a < ab);
+    assert!(ab > a);
+    assert!(a <= a);
+    assert_eq!(a.partial_cmp(&ab), Some(Ordering::Less));
+    assert_eq!(ab.partial_cmp(&a), Some(Ordering::Greater));
+    assert_eq!(c.partial_cmp(&ab), None);
+    assert_eq!(c.partial_cmp(&a), None);
+    assert!(SubsetOrd(Flags8::empty()) <= c);
+    assert_eq!(SubsetOrd::from(Flags8::C), c);
+
+    // Least upper bound of a chain
+    let join = [a, ab, c]
+        .iter()
+        .fold(Flags8::empty(), |acc, flags| acc | flags.0);
+    assert!([a, ab, c].iter().all(|flags| *flags <= SubsetOrd(join)));
+}
+
+// =============================================================================
+// Character Code Tests (to_char_string / from_char_string)
+// =============================================================================
+
+neobit! {
+    /// `ls -l` style mode bits
+    pub struct ModeFlags: u16 {
+        const OWNER_READ  = 0o400 => 'r';
+        const OWNER_WRITE = 0o200 => 'w';
+        const OWNER_EXEC  = 0o100 => 'x';
+        const GROUP_READ  = 0o040 => 'r';
+        const GROUP_WRITE = 0o020 => 'w';
+        const GROUP_EXEC  = 0o010 => 'x';
+        const OTHER_READ  = 0o004 => 'r';
+        const OTHER_WRITE = 0o002 => 'w';
+        const OTHER_EXEC  = 0o001 => 'x';
+        const STICKY      = 0o1000;
+    }
+}
+
+neobit! {
+    #[neobit(unset_char = '.')]
+    pub struct TcpSummary: i8 {
+        const FIN = 0x01 => 'F';
+        const SYN = 0x02 => 'S';
+        const RST = 0x04 => 'R';
+        const PSH = 0x08 => 'P';
+        const ACK = 0x10 => 'A';
+        const URG = 0x20 => 'U';
+        alias SYNACK = Self::SYN.union(Self::ACK);
+    }
+}
+
+neobit! {
+    pub struct UnicodeMarks: u8 {
+        const DONE = 1 => '✓';
+        const STAR = 2 => '★';
+    }
+}
+
+#[test]
+fn test_to_char_string() {
+    let mode = ModeFlags::from_bits_retain(0o750);
+    assert_eq!(mode.to_char_string(), "rwxr-x---");
+    assert_eq!(ModeFlags::all().to_char_string(), "rwxrwxrwx");
+    assert_eq!(ModeFlags::STICKY.to_char_string(), "---------");
+    assert_eq!(ModeFlags::CHARS.len(), 9);
+
+    assert_eq!(TcpSummary::SYNACK.to_char_string(), ".S..A.");
+    assert_eq!(TcpSummary::from(-1).to_char_string(), "FSRPAU");
+    assert_eq!(
+        format!("{}", TcpSummary::empty().to_char_string()),
+        "......"
+    );
+    assert_eq!(
+        format!("{:?}", TcpSummary::FIN.to_char_string()),
+        "\"F.....\""
+    );
+
+    assert_eq!(UnicodeMarks::all().to_char_string(), "✓★");
+    assert_eq!(UnicodeMarks::STAR.to_char_string().as_str(), "-★");
+}
+
+#[test]
+fn test_from_char_string() {
+    assert_eq!(
+        ModeFlags::from_char_string("rwxr-x---"),
+        Ok(ModeFlags::from(0o750))
+    );
+    assert_eq!(
+        TcpSummary::from_char_string(".S..A."),
+        Ok(TcpSummary::SYNACK)
+    );
+    assert_eq!(UnicodeMarks::from_char_string("✓-"), Ok(UnicodeMarks::DONE));
+
+    for bits in 0..0o1000 {
+        let mode = ModeFlags::from(bits);
+        assert_eq!(
+            ModeFlags::from_char_string(&mode.to_char_string()),
+            Ok(mode)
+        );
+    }
+}
+
+#[test]
+fn test_from_char_string_errors() {
+    use neobit::CharStringError;
+
+    assert_eq!(
+        ModeFlags::from_char_string("rwxr-xr-X"),
+        Err(CharStringError::InvalidChar { position: 8 })
+    );
+    // Characters are positional: `w` is not valid in a read slot
+    assert_eq!(
+        ModeFlags::from_char_string("wr-------"),
+        Err(CharStringError::InvalidChar { position: 0 })
+    );
+    assert_eq!(
+        ModeFlags::from_char_string("rwx"),
+        Err(CharStringError::WrongLength {
+            expected: 9,
+            found: 3
+        })
+    );
+    assert_eq!(
+        TcpSummary::from_char_string("......."),
+        Err(CharStringError::WrongLength {
+            expected: 6,
+            found: 7
+        })
+    );
+    assert_eq!(
+        TcpSummary::from_char_string("-S..A."),
+        Err(CharStringError::InvalidChar { position: 0 })
+    );
+    assert_eq!(
+        CharStringError::WrongLength {
+            expected: 6,
+            found: 7
+        }
+        .to_string(),
+        "expected 6 characters, found 7"
+    );
+}
+
+#[test]
+fn test_write_char_string() {
+    let mut buf = [0u8; 9];
+    assert_eq!(
+        ModeFlags::from(0o644).write_char_string(&mut buf),
+        Ok("rw-r--r--")
+    );
+    assert_eq!(
+        ModeFlags::from(0o644).write_char_string(&mut buf[..8]),
+        Err(neobit::BufferTooSmall)
+    );
+
+    let mut buf = [0u8; 6];
+    assert_eq!(UnicodeMarks::all().write_char_string(&mut buf), Ok("✓★"));
+}
+
+// =============================================================================
+// Conversion Tests (cast / widen / try_narrow)
+// =============================================================================
+
+// Sibling of `CFlags` with an unsigned repr
+neobit! {
+    pub struct UFlags: u32 {
+        const OPTION_A = 0x01;
+        const OPTION_B = 0x02;
+        const OPTION_C = 0x04;
+    }
+}
+
+// Sibling of `CFlags` in the wire format
+neobit! {
+    pub struct WireFlags: u16 {
+        const OPTION_A = 0x01;
+        const OPTION_B = 0x02;
+        const OPTION_C = 0x04;
+    }
+}
+
+neobit! {
+    pub struct WideFlags: u64 {
+        const OPTION_A = 0x01;
+        const OPTION_B = 0x02;
+        const OPTION_C = 0x04;
+    }
+}
+
+#[test]
+fn test_cast_same_width() {
+    let c = CFlags::OPTION_A | CFlags::OPTION_C;
+    let u: UFlags = c.cast();
+    assert_eq!(u, UFlags::OPTION_A | UFlags::OPTION_C);
+    assert_eq!(u.cast::<CFlags>(), c);
+
+    // Every bit is kept, including the sign bit
+    let negative = CFlags::from(-1);
+    assert_eq!(negative.cast::<UFlags>().bits(), u32::MAX);
+    assert_eq!(negative.cast::<UFlags>().cast::<CFlags>(), negative);
+}
+
+#[test]
+fn test_widen_zero_extends() {
+    let negative = CFlags::from(i32::MIN | 1);
+    let wide: WideFlags = negative.widen();
+    assert_eq!(wide.bits(), 0x8000_0001);
+    assert_ne!(wide.bits(), negative.bits() as u64); // `as` sign-extends
+
+    let wire = WireFlags::OPTION_B;
+    assert_eq!(wire.widen::<CFlags>(), CFlags::OPTION_B);
+    assert_eq!(wire.widen::<WireFlags>(), wire);
+    assert_eq!(SignedFlags8::from(-1).widen::<Flags128>().bits(), 0xFF);
+}
+
+#[test]
+fn test_try_narrow() {
+    let c = CFlags::OPTION_A | CFlags::OPTION_B;
+    assert_eq!(
+        c.try_narrow::<WireFlags>(),
+        Ok(WireFlags::OPTION_A | WireFlags::OPTION_B)
+    );
+    assert_eq!(
+        CFlags::from(0xFFFF)
+            .try_narrow::<WireFlags>()
+            .unwrap()
+            .bits(),
+        0xFFFF
+    );
+    assert_eq!(
+        WideFlags::OPTION_C.try_narrow::<Flags128>(),
+        Ok(Flags128::from(0x04))
+    );
+
+    let err = CFlags::from(-1).try_narrow::<WireFlags>().unwrap_err();
+    assert_eq!(err.flags(), CFlags::from(-1));
+    assert_eq!(err.target_bits(), 16);
+
+    let err = (CFlags::OPTION_A | CFlags::from(0x1_0000))
+        .try_narrow::<WireFlags>()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "flags OPTION_A | 0x10000 do not fit in 16 bits"
+    );
+}
+
+// =============================================================================
+// Visibility and Hidden Flag Tests
+// =============================================================================
+
+mod driver {
+    use neobit::neobit;
+
+    neobit! {
+        #[neobit(flag_enum = DriverFlag { Done, Error, Retry }, consts = driver_consts)]
+        pub struct DriverFlags: u8 {
+            /// Transfer complete
+            const DONE = 0x01 => 'd';
+            #[w1c]
+            const ERROR = 0x02 => 'e';
+            #[hidden]
+            const TEST_HOOK = 0x40 => 't';
+            /// Driver bookkeeping
+            #[allow(dead_code)]
+            #[hidden]
+            pub(crate) const BUSY = 0x80;
+            pub(crate) const RETRY = 0x04;
+            #[hidden]
+            pub(self) const SELF_TEST = 0x20;
+            pub(super) alias FINISHED = DONE;
+            #[hidden]
+            alias COMPLETE = DONE;
+        }
+    }
+
+    pub fn busy() -> DriverFlags {
+        DriverFlags::BUSY
+    }
+
+    // `pub(self)` flags are private, see the `compile_fail` example in the
+    // `neobit!` docs
+    pub fn self_test() -> DriverFlags {
+        DriverFlags::SELF_TEST
+    }
+}
+
+use driver::{driver_consts, DriverFlag, DriverFlags};
+
+#[test]
+fn test_hidden_flags_keep_constants() {
+    assert_eq!(DriverFlags::TEST_HOOK.bits(), 0x40);
+    assert_eq!(driver::busy().bits(), 0x80);
+    assert_eq!(driver::self_test().bits(), 0x20);
+    assert_eq!(DriverFlags::COMPLETE, DriverFlags::DONE);
+    assert_eq!(DriverFlags::FINISHED, DriverFlags::DONE);
+}
+
+#[test]
+fn test_hidden_flags_not_listed() {
+    assert_eq!(DriverFlags::all().bits(), 0x07);
+    assert_eq!(<DriverFlags as neobit::Flags>::FLAG_COUNT, 3);
+    assert_eq!(
+        <DriverFlags as neobit::Flags>::FLAGS
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>(),
+        ["DONE", "ERROR", "RETRY"]
+    );
+    assert_eq!(DriverFlag::ALL_VARIANTS.len(), 3);
+    assert_eq!(<DriverFlags as neobit::Flags>::W1C_MASK, DriverFlags::ERROR);
+    assert_eq!(
+        DriverFlags::from_bits(0x40),
+        None,
+        "hidden bits are unknown"
+    );
+    assert_eq!(DriverFlags::TEST_HOOK.complement_known().bits(), 0x07);
+}
+
+#[test]
+fn test_hidden_flags_not_parsed() {
+    assert_eq!(DriverFlags::from_name("DONE"), Some(DriverFlags::DONE));
+    assert_eq!(DriverFlags::from_name("FINISHED"), Some(DriverFlags::DONE));
+    assert_eq!(DriverFlags::from_name("TEST_HOOK"), None);
+    assert_eq!(DriverFlags::from_name("COMPLETE"), None);
+    assert_eq!(DriverFlags::all().to_char_string(), "de");
+    assert_eq!(
+        DriverFlags::from_char_string("dt"),
+        Err(neobit::CharStringError::InvalidChar { position: 1 })
+    );
+}
+
+#[test]
+fn test_hidden_flags_debug() {
+    let flags = DriverFlags::DONE | DriverFlags::TEST_HOOK;
+    assert_eq!(format!("{:?}", flags), "DriverFlags(DONE | 0x40)");
+}
+
+#[test]
+fn test_consts_module_only_public() {
+    assert_eq!(driver_consts::DONE, 0x01);
+    assert_eq!(driver_consts::TEST_HOOK, 0x40);
+}
+
+// =============================================================================
+// Iteration Tests (iter / iter_names)
+// =============================================================================
+
+neobit! {
+    pub struct Overlap: u8 {
+        const A = 0b0001;
+        const B = 0b0010;
+        const AB = 0b0011;
+        const C = 0b0100;
+        const BC = 0b0110;
+    }
+}
+
+#[test]
+fn test_iter() {
+    assert_eq!(Flags8::empty().iter().count(), 0);
+    assert_eq!(
+        (Flags8::A | Flags8::C).iter().collect::<Vec<_>>(),
+        [Flags8::A, Flags8::C]
+    );
+    assert_eq!(
+        Flags8::from(0x81).iter().collect::<Vec<_>>(),
+        [Flags8::A, Flags8::from(0x80)]
+    );
+    assert_eq!(
+        Flags8::from(0x80).iter().collect::<Vec<_>>(),
+        [Flags8::from(0x80)]
+    );
+}
+
+#[test]
+fn test_iter_overlapping_flags() {
+    // Flags are yielded while they still add a bit, as in bitflags
+    let flags = Overlap::A | Overlap::B | Overlap::C;
+    assert_eq!(
+        flags.iter_names().map(|(name, _)| name).collect::<Vec<_>>(),
+        ["A", "B", "C"]
+    );
+    assert_eq!(
+        Overlap::BC
+            .iter_names()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>(),
+        ["B", "C"]
+    );
+}
+
+#[test]
+fn test_iter_names() {
+    let mut names = (Flags8::B | Flags8::from(0x80)).iter_names();
+    assert_eq!(names.next(), Some(("B", Flags8::B)));
+    assert_eq!(names.next(), None);
+    assert_eq!(names.remaining(), Flags8::from(0x80));
+}
+
+#[test]
+fn test_iter_traits() {
+    let mut seen = Flags8::empty();
+    for flag in Flags8::A | Flags8::D {
+        seen |= flag;
+    }
+    assert_eq!(seen, Flags8::A | Flags8::D);
+
+    let collected: Flags8 = vec![Flags8::A, Flags8::B].into_iter().collect();
+    assert_eq!(collected, Flags8::A | Flags8::B);
+
+    let mut extended = Flags8::C;
+    extended.extend((Flags8::A | Flags8::from(0x80)).iter());
+    assert_eq!(extended.bits(), 0x85);
+}
+
+neobit! {
+    #[neobit(complement = masked)]
+    pub struct MaskedFlags: i8 {
+        const A = 0b01;
+        const B = 0b10;
+    }
+}
+
+#[test]
+fn test_complement_masked() {
+    const NOT_A: MaskedFlags = MaskedFlags::A.complement();
+    assert_eq!(NOT_A, MaskedFlags::B);
+    assert_eq!(!MaskedFlags::A, MaskedFlags::B);
+    assert_eq!(!MaskedFlags::from(-1), MaskedFlags::empty());
+    assert_eq!(!MaskedFlags::empty(), MaskedFlags::all());
+}
+
+// =============================================================================
+// Impl Selection Tests (no_from / no_ord / debug)
+// =============================================================================
+
+neobit! {
+    /// Safety-critical flags: no lossless `From<u8>`, ordered by inclusion,
+    /// own `Debug`
+    #[neobit(no_from, no_ord, debug = "custom")]
+    pub struct SafetyFlags: u8 {
+        const ARMED = 0b01;
+        const FAULT = 0b10;
+    }
+}
+
+// Each impl below conflicts with the generated one unless it is turned off
+impl From<u8> for SafetyFlags {
+    fn from(bits: u8) -> Self {
+        Self::from_bits_truncate(bits)
+    }
+}
+
+impl PartialOrd for SafetyFlags {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        neobit::SubsetOrd(*self).partial_cmp(&neobit::SubsetOrd(*other))
+    }
+}
+
+impl core::fmt::Debug for SafetyFlags {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Safety[{:02b}]", self.bits())
+    }
+}
+
+#[test]
+fn test_impl_selection() {
+    assert_eq!(SafetyFlags::from(0xFF), SafetyFlags::all());
+    assert_eq!(u8::from(SafetyFlags::ARMED), 0b01);
+    assert_eq!(SafetyFlags::ARMED.partial_cmp(&SafetyFlags::FAULT), None);
+    assert!(SafetyFlags::ARMED < SafetyFlags::all());
+    assert_eq!(format!("{:?}", SafetyFlags::FAULT), "Safety[10]");
+
+    let mut buf = [0u8; 32];
+    assert_eq!(
+        SafetyFlags::all().write_names(&mut buf),
+        Ok("SafetyFlags(ARMED | FAULT)")
+    );
+}
+
+#[test]
+fn test_default_impls_kept() {
+    // Without options the generated type is ordered by its bits
+    assert!(Flags8::B > Flags8::A);
+    assert_eq!(Flags8::A.max(Flags8::D), Flags8::D);
+    assert_eq!(Flags8::from(0x03), Flags8::A | Flags8::B);
+}
+
+// =============================================================================
+// Unknown Bits Policy Tests (unknown = retain / truncate / reject)
+// =============================================================================
+
+neobit! {
+    /// Parsed from untrusted packets: unknown bits are an error
+    #[neobit(unknown = reject)]
+    pub struct PacketFlags: u8 {
+        const SYN = 0b001;
+        const ACK = 0b010;
+        const FIN = 0b100;
+    }
+}
+
+neobit! {
+    /// Reserved register bits read as garbage and are dropped
+    #[neobit(unknown = truncate)]
+    pub struct StatusReg: u16 {
+        const READY = 1 << 0;
+        const BUSY = 1 << 1;
+    }
+}
+
+neobit! {
+    #[neobit(unknown = retain)]
+    pub struct RawFlags: u8 {
+        const A = 0b01;
+    }
+}
+
+neobit! {
+    #[neobit(no_from, unknown = truncate)]
+    pub struct NoConversion: u8 {
+        const A = 0b01;
+    }
+}
+
+#[test]
+fn test_unknown_reject() {
+    use core::convert::TryFrom;
+
+    assert_eq!(
+        PacketFlags::try_from(0b011),
+        Ok(PacketFlags::SYN | PacketFlags::ACK)
+    );
+    assert_eq!(PacketFlags::try_from(0), Ok(PacketFlags::empty()));
+
+    let err = PacketFlags::try_from(0x41).unwrap_err();
+    assert_eq!(err.flags(), PacketFlags::from_bits_retain(0x41));
+    assert_eq!(err.unknown(), 0x40);
+    assert_eq!(err.to_string(), "unknown bits 0x40 in SYN | 0x40");
+
+    // Converting back to the integer is unaffected
+    assert_eq!(u8::from(PacketFlags::FIN), 0b100);
+}
+
+/// A decoder written once against `TryFrom`, following each type's policy
+fn decode<F: core::convert::TryFrom<u16>>(bits: u16) -> Option<F> {
+    F::try_from(bits).ok()
+}
+
+#[test]
+fn test_unknown_truncate() {
+    assert_eq!(StatusReg::from(0xFF03), StatusReg::READY | StatusReg::BUSY);
+    assert_eq!(decode::<StatusReg>(0x8000), Some(StatusReg::empty()));
+    let status: StatusReg = 0x0101.into();
+    assert_eq!(status, StatusReg::READY);
+}
+
+#[test]
+fn test_unknown_cross_type() {
+    // Into a rejecting type only `try_narrow()` compiles, and it checks the bits
+    assert_eq!(
+        (Flags8::A | Flags8::B).try_narrow::<PacketFlags>(),
+        Ok(PacketFlags::SYN | PacketFlags::ACK)
+    );
+    let err = (Flags8::A | Flags8::D)
+        .try_narrow::<PacketFlags>()
+        .unwrap_err();
+    assert!(err.is_unknown());
+    assert_eq!(err.flags(), Flags8::A | Flags8::D);
+    assert_eq!(
+        err.to_string(),
+        "flags A | D have bits unknown to the target"
+    );
+    let err = WireFlags::from(0x101)
+        .try_narrow::<PacketFlags>()
+        .unwrap_err();
+    assert!(!err.is_unknown());
+
+    // Truncating types drop unknown bits whichever way they are converted
+    assert_eq!(
+        (Flags8::A | Flags8::C).widen::<StatusReg>(),
+        StatusReg::READY
+    );
+    assert_eq!(
+        WireFlags::from(0xFF01).cast::<StatusReg>(),
+        StatusReg::READY
+    );
+    assert_eq!(
+        WireFlags::from(0x0102).try_narrow::<StatusReg>(),
+        Ok(StatusReg::BUSY)
+    );
+}
+
+#[test]
+fn test_unknown_retain() {
+    assert_eq!(RawFlags::from(0x81).bits(), 0x81);
+    assert_eq!(NoConversion::from_bits_truncate(0xFF), NoConversion::A);
+}
+
+// =============================================================================
+// Zero Name Tests (zero = NAME)
+// =============================================================================
+
+neobit! {
+    /// Pin mode field, where input is the all-zero mode
+    #[neobit(zero = MODE_INPUT)]
+    pub struct PinMode: u8 {
+        const MODE_INPUT = 0b00;
+        const MODE_OUTPUT = 0b01;
+        const MODE_ALT = 0b10;
+    }
+}
+
+#[test]
+fn test_zero_name_debug() {
+    assert_eq!(format!("{:?}", PinMode::MODE_INPUT), "PinMode(MODE_INPUT)");
+    assert_eq!(format!("{:?}", PinMode::empty()), "PinMode(MODE_INPUT)");
+    assert_eq!(
+        format!("{:?}", PinMode::MODE_OUTPUT),
+        "PinMode(MODE_OUTPUT)"
+    );
+    assert_eq!(format!("{:?}", PinMode::from(0x80)), "PinMode(0x80)");
+
+    let mut buf = [0u8; 32];
+    assert_eq!(
+        PinMode::empty().write_names(&mut buf),
+        Ok("PinMode(MODE_INPUT)")
+    );
+
+    // Without the option the empty value stays `empty`
+    assert_eq!(format!("{:?}", Flags8::empty()), "Flags8(empty)");
+}
+
+#[test]
+fn test_zero_name_display_and_parsing() {
+    let err = PinMode::MODE_INPUT
+        .require_any(PinMode::empty())
+        .unwrap_err();
+    assert_eq!(err.to_string(), "missing any of: MODE_INPUT");
+
+    assert_eq!(PinMode::from_name("MODE_INPUT"), Some(PinMode::empty()));
+    assert_eq!(PinMode::from_name("empty"), None);
+}
+
+#[test]
+fn test_zero_name_contains() {
+    // Every value contains the zero flag; equality tells it apart
+    assert!(PinMode::MODE_ALT.contains(PinMode::MODE_INPUT));
+    assert!(!PinMode::MODE_ALT.intersects(PinMode::MODE_INPUT));
+    assert_ne!(PinMode::MODE_ALT, PinMode::MODE_INPUT);
+    assert!(PinMode::MODE_INPUT.is_empty());
+}
+
+// =============================================================================
+// Pattern Tests (FlagPattern)
+// =============================================================================
+
+use neobit::FlagPattern;
+
+// Filter rules declared statically
+const SYN_SCAN: FlagPattern<TcpSummary> =
+    TcpSummary::pattern(TcpSummary::SYN, TcpSummary::ACK.union(TcpSummary::RST));
+const ESTABLISHED: FlagPattern<TcpSummary> = TcpSummary::pattern(TcpSummary::ACK, TcpSummary::SYN);
+
+#[test]
+fn test_pattern_matches() {
+    assert!(SYN_SCAN.matches(TcpSummary::SYN));
+    assert!(SYN_SCAN.matches(TcpSummary::SYN | TcpSummary::URG));
+    assert!(!SYN_SCAN.matches(TcpSummary::SYNACK));
+    assert!(!SYN_SCAN.matches(TcpSummary::FIN));
+    assert!(ESTABLISHED.matches(TcpSummary::ACK | TcpSummary::PSH));
+    assert!(
+        TcpSummary::pattern(TcpSummary::empty(), TcpSummary::empty()).matches(TcpSummary::from(-1))
+    );
+}
+
+#[test]
+fn test_pattern_builders_accumulate() {
+    let fin_ack = FlagPattern::new(TcpSummary::FIN, TcpSummary::empty()).must_set(TcpSummary::ACK);
+    assert_eq!(fin_ack.required(), TcpSummary::FIN | TcpSummary::ACK);
+    let scan = FlagPattern::new(TcpSummary::SYN, TcpSummary::ACK).must_clear(TcpSummary::RST);
+    assert_eq!(scan, SYN_SCAN);
+
+    // Signed and 128-bit types combine like the others
+    let high = TcpSummary::from(i8::MIN);
+    const HIGH: FlagPattern<TcpSummary> =
+        TcpSummary::pattern(TcpSummary::from_bits_retain(i8::MIN), TcpSummary::empty());
+    assert_eq!(
+        HIGH.must_set(TcpSummary::SYN).required(),
+        high | TcpSummary::SYN
+    );
+    assert!(high.satisfies(HIGH));
+    const WIDE: FlagPattern<Flags128> =
+        Flags128::pattern(Flags128::empty(), Flags128::from_bits_retain(1 << 127))
+            .union(Flags128::pattern(Flags128::empty(), Flags128::A));
+    assert_eq!(WIDE.forbidden(), Flags128::A | Flags128::from(1 << 127));
+}
+
+#[test]
+fn test_pattern_const() {
+    const IS_SCAN: bool = TcpSummary::SYN.satisfies(SYN_SCAN);
+    const IS_NOT_SCAN: bool = TcpSummary::SYNACK.satisfies(SYN_SCAN);
+    const BOTH: FlagPattern<TcpSummary> = SYN_SCAN.union(ESTABLISHED);
+    const COMMON: FlagPattern<TcpSummary> = SYN_SCAN.intersection(ESTABLISHED);
+    const SATISFIABLE: bool = BOTH.is_satisfiable();
+    assert!(IS_SCAN);
+    assert!(!IS_NOT_SCAN);
+    assert!(!SATISFIABLE);
+    assert_eq!(COMMON.required(), TcpSummary::empty());
+    for value in [TcpSummary::SYN, TcpSummary::SYNACK, TcpSummary::from(-1)] {
+        assert_eq!(value.satisfies(SYN_SCAN), SYN_SCAN.matches(value));
+    }
+}
+
+#[test]
+fn test_pattern_matching_bits() {
+    assert_eq!(
+        SYN_SCAN.matching_bits(TcpSummary::SYN | TcpSummary::RST),
+        TcpSummary::SYN | TcpSummary::ACK
+    );
+    assert_eq!(SYN_SCAN.matching_bits(TcpSummary::RST), TcpSummary::ACK);
+    assert_eq!(SYN_SCAN.required(), TcpSummary::SYN);
+    assert_eq!(SYN_SCAN.forbidden(), TcpSummary::ACK | TcpSummary::RST);
+}
+
+#[test]
+fn test_pattern_union_intersection() {
+    let both = SYN_SCAN.union(ESTABLISHED);
+    assert!(!both.is_satisfiable());
+    assert!(!both.matches(TcpSummary::SYN));
+    assert!(!both.matches(TcpSummary::ACK));
+
+    let common = SYN_SCAN.intersection(ESTABLISHED);
+    assert_eq!(
+        common,
+        FlagPattern::new(TcpSummary::empty(), TcpSummary::empty())
+    );
+    assert!(common.is_satisfiable());
+
+    let fin = FlagPattern::new(TcpSummary::FIN, TcpSummary::empty());
+    assert_eq!(
+        SYN_SCAN.union(fin),
+        TcpSummary::pattern(
+            TcpSummary::SYN | TcpSummary::FIN,
+            TcpSummary::ACK | TcpSummary::RST
+        )
+    );
+}
+
+#[test]
+fn test_pattern_debug() {
+    assert_eq!(format!("{:?}", SYN_SCAN), "{+SYN -RST -ACK}");
+    assert_eq!(
+        format!(
+            "{:?}",
+            TcpSummary::pattern(TcpSummary::empty(), TcpSummary::empty())
+        ),
+        "{}"
+    );
+    assert_eq!(
+        format!("{:?}", FlagPattern::new(Flags8::A, Flags8::from(0x80))),
+        "{+A -0x80}"
+    );
+}
+
+// =============================================================================
+// Partial Update Tests (Masked)
+// =============================================================================
+
+use neobit::Masked;
+
+#[test]
+fn test_masked_apply() {
+    let update = Masked::new(Flags8::A, Flags8::A | Flags8::B);
+    assert_eq!(update.apply(Flags8::B | Flags8::C), Flags8::A | Flags8::C);
+    assert_eq!(update.apply(Flags8::empty()), Flags8::A);
+    assert_eq!(update.to_set(), Flags8::A);
+    assert_eq!(update.to_clear(), Flags8::B);
+
+    // Bits outside the mask are never touched, unknown bits included
+    assert_eq!(
+        update.apply(Flags8::from(0x80)),
+        Flags8::A | Flags8::from(0x80)
+    );
+}
+
+#[test]
+fn test_masked_from_set_clear() {
+    let update = Masked::from((Flags8::A | Flags8::C, Flags8::B | Flags8::C));
+    assert_eq!(update.to_set(), Flags8::A);
+    assert_eq!(update.to_clear(), Flags8::B | Flags8::C);
+    assert_eq!(update, Masked::set_clear(Flags8::A, Flags8::B | Flags8::C));
+    assert_eq!(update.apply(Flags8::all()), Flags8::A | Flags8::D);
+}
+
+#[test]
+fn test_masked_then() {
+    let defaults = Masked::from((Flags8::A | Flags8::B, Flags8::D));
+    let overrides = Masked::from((Flags8::D, Flags8::A));
+    let layered = defaults.then(overrides);
+
+    for &base in &[Flags8::empty(), Flags8::all(), Flags8::C] {
+        assert_eq!(layered.apply(base), overrides.apply(defaults.apply(base)));
+    }
+    assert_eq!(layered.to_set(), Flags8::B | Flags8::D);
+    assert_eq!(layered.to_clear(), Flags8::A);
+}
+
+#[test]
+fn test_masked_debug() {
+    let update = Masked::from((TcpSummary::SYN, TcpSummary::ACK));
+    assert_eq!(format!("{:?}", update), "{+SYN -ACK}");
+    assert_eq!(
+        format!("{:?}", Masked::new(Flags8::empty(), Flags8::empty())),
+        "{}"
+    );
+}
+
+// =============================================================================
+// Bulk Operation Tests (FlagSliceExt)
+// =============================================================================
+
+use neobit::FlagSliceExt;
+
+#[test]
+fn test_slice_queries() {
+    let values = [
+        Flags8::A,
+        Flags8::A | Flags8::B,
+        Flags8::C,
+        Flags8::from(0x80),
+        Flags8::empty(),
+    ];
+    assert_eq!(values.count_containing(Flags8::A), 2);
+    assert_eq!(values.count_containing(Flags8::A | Flags8::B), 1);
+    assert_eq!(values.count_containing(Flags8::empty()), 5);
+    assert_eq!(values.count_intersecting(Flags8::B | Flags8::C), 2);
+    assert_eq!(values.count_intersecting(Flags8::from(0x80)), 1);
+    assert_eq!(values.count_intersecting(Flags8::empty()), 0);
+
+    let containing: Vec<usize> = values.indices_containing(Flags8::A).collect();
+    assert_eq!(containing, [0, 1]);
+    let intersecting: Vec<usize> = values
+        .indices_intersecting(Flags8::B | Flags8::from(0x80))
+        .collect();
+    assert_eq!(intersecting, [1, 3]);
+}
+
+#[test]
+fn test_slice_updates() {
+    let mut values = vec![SignedFlags8::A, SignedFlags8::B, SignedFlags8::from(-128)];
+    values.insert_all(SignedFlags8::C);
+    assert_eq!(values.count_containing(SignedFlags8::C), 3);
+
+    values[1..].remove_all(SignedFlags8::B | SignedFlags8::C);
+    assert_eq!(
+        values,
+        [
+            SignedFlags8::A | SignedFlags8::C,
+            SignedFlags8::empty(),
+            SignedFlags8::from(-128)
+        ]
+    );
+}
+
+// =============================================================================
+// Bit Position Tests (bit(n) / msb0)
+// =============================================================================
+
+neobit! {
+    /// Numbered as in the datasheet, bit 0 is the LSB
+    pub struct SpiControl: u16 {
+        const CPHA     = bit(0);
+        const CPOL     = bit(1);
+        const LSBFIRST = bit(7);
+        const BIDIMODE = bit(15);
+        const MODE3    = bit(0) | bit(1);
+    }
+}
+
+neobit! {
+    /// Numbered IEEE style, bit 0 is the MSB
+    #[neobit(msb0, consts = msr_consts)]
+    pub struct MachineState: u32 {
+        const SF = bit(0);
+        const EE = bit(16);
+        const LE = bit(31);
+    }
+}
+
+neobit! {
+    #[neobit(msb0)]
+    pub struct SignedMsb: i8 {
+        const TOP = bit(0);
+        const BOTTOM = bit(7);
+    }
+}
+
+#[test]
+fn test_bit_positions() {
+    assert_eq!(SpiControl::CPHA.bits(), 0x0001);
+    assert_eq!(SpiControl::LSBFIRST.bits(), 0x0080);
+    assert_eq!(SpiControl::BIDIMODE.bits(), 0x8000);
+    assert_eq!(SpiControl::MODE3, SpiControl::CPHA | SpiControl::CPOL);
+}
+
+#[test]
+fn test_msb0_positions() {
+    assert_eq!(MachineState::SF.bits(), 0x8000_0000);
+    assert_eq!(MachineState::EE.bits(), 0x0000_8000);
+    assert_eq!(MachineState::LE.bits(), 0x0000_0001);
+    assert_eq!(msr_consts::SF, 0x8000_0000);
+    assert_eq!(msr_consts::LE, 1);
+
+    assert_eq!(SignedMsb::TOP.bits(), i8::MIN);
+    assert_eq!(SignedMsb::BOTTOM.bits(), 1);
+}
+
+// =============================================================================
+// Constant-Time Tests (ct_eq / ct_contains / ct_intersects / ct_select)
+// =============================================================================
+
+use neobit::Choice;
+
+#[test]
+fn test_ct_matches_branching() {
+    let values = [
+        SignedFlags8::empty(),
+        SignedFlags8::A,
+        SignedFlags8::A | SignedFlags8::C,
+        SignedFlags8::from(-128),
+        SignedFlags8::from(-1),
+    ];
+    for &a in &values {
+        for &b in &values {
+            assert_eq!(bool::from(a.ct_eq(b)), a == b);
+            assert_eq!(bool::from(a.ct_contains(b)), a.contains(b));
+            assert_eq!(bool::from(a.ct_intersects(b)), a.intersects(b));
+        }
+    }
+}
+
+#[test]
+fn test_ct_select() {
+    let a = Flags128::from(u128::MAX);
+    let b = Flags128::empty();
+    assert_eq!(Flags128::ct_select(a, b, Choice::from(0)), a);
+    assert_eq!(Flags128::ct_select(a, b, Choice::from(1)), b);
+    assert_eq!(
+        SignedFlags8::ct_select(SignedFlags8::A, SignedFlags8::from(-128), Choice::from(1)),
+        SignedFlags8::from(-128)
+    );
+}
+
+#[test]
+fn test_choice_ops() {
+    let yes = Flags8::A.ct_eq(Flags8::A);
+    let no = Flags8::A.ct_eq(Flags8::B);
+    assert_eq!(yes.unwrap_u8(), 1);
+    assert_eq!(no.unwrap_u8(), 0);
+    assert_eq!((yes & no).unwrap_u8(), 0);
+    assert_eq!((yes | no).unwrap_u8(), 1);
+    assert_eq!((!yes).unwrap_u8(), 0);
+    assert_eq!((!no).unwrap_u8(), 1);
+    assert_eq!(Choice::from(3).unwrap_u8(), 1);
+}
+
+#[cfg(feature = "subtle")]
+#[test]
+fn test_subtle_traits() {
+    use subtle::{ConditionallySelectable, ConstantTimeEq};
+
+    assert_eq!(ConstantTimeEq::ct_eq(&Flags8::A, &Flags8::A).unwrap_u8(), 1);
+    assert_eq!(ConstantTimeEq::ct_eq(&Flags8::A, &Flags8::B).unwrap_u8(), 0);
+    let chosen = Flags8::conditional_select(&Flags8::A, &Flags8::B, subtle::Choice::from(1));
+    assert_eq!(chosen, Flags8::B);
+}
+
+// =============================================================================
+// Conformance Suite (neobit_test_suite!)
+// =============================================================================
+
+use neobit::neobit_test_suite;
+
+neobit_test_suite!(mod flags8_laws: Flags8);
+neobit_test_suite!(mod flags128_laws: Flags128);
+neobit_test_suite!(mod signed8_laws: SignedFlags8);
+neobit_test_suite!(mod known_complement_laws: KnownComplementFlags);
+neobit_test_suite!(mod masked_laws: MaskedFlags);
+neobit_test_suite!(mod overlap_laws: Overlap);
+neobit_test_suite!(mod tcp_laws: TcpSummary);
+neobit_test_suite!(mod spi_laws: SpiControl);
+neobit_test_suite!(mod driver_laws: driver::DriverFlags);
+neobit_test_suite!(mod safety_laws: SafetyFlags);
+neobit_test_suite!(mod packet_laws: PacketFlags);
+neobit_test_suite!(mod status_reg_laws: StatusReg);
+neobit_test_suite!(mod raw_laws: RawFlags);
+neobit_test_suite!(mod pin_mode_laws: PinMode);